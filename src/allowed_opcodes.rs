@@ -0,0 +1,73 @@
+//! Enforce a contract with runtimes that only support a subset of SPIR-V, by failing if a module
+//! uses any instruction that isn't explicitly allowed.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use anyhow::Context as _;
+
+use crate::inspect;
+
+/// How many kinds of disallowed instruction to describe in the error.
+const SHOWN_VIOLATIONS: usize = 5;
+
+/// Read the allowed opcodes: one per line, with or without the `Op` prefix. Blank lines and
+/// lines starting with `#` are ignored.
+fn load(path: &Path) -> anyhow::Result<HashSet<String>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read allowed opcodes '{}'", path.display()))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.strip_prefix("Op").unwrap_or(line).to_owned())
+        .collect())
+}
+
+/// Fail if the module contains any instruction whose opcode isn't in the allowed list. This
+/// includes module-level instructions, like `OpCapability` and `OpEntryPoint`.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn check(module_path: &Path, allowed_path: &Path) -> anyhow::Result<()> {
+    let allowed = load(allowed_path)?;
+    let module = inspect::load(module_path)?;
+
+    let mut violations: BTreeMap<&str, (usize, Option<u32>)> = BTreeMap::new();
+    for instruction in module.all_inst_iter() {
+        if allowed.contains(instruction.class.opname) {
+            continue;
+        }
+        let (count, example_id) = violations.entry(instruction.class.opname).or_default();
+        *count = count.saturating_add(1);
+        if example_id.is_none() {
+            *example_id = instruction.result_id;
+        }
+    }
+
+    if violations.is_empty() {
+        tracing::info!("  Module only uses allowed opcodes");
+        return Ok(());
+    }
+
+    let total: usize = violations.values().map(|&(count, _)| count).sum();
+    let mut descriptions: Vec<String> = violations
+        .iter()
+        .take(SHOWN_VIOLATIONS)
+        .map(|(opname, &(count, example_id))| {
+            let example = example_id.map_or_else(String::new, |id| format!(", eg %{id}"));
+            format!("Op{opname}: {count} instruction(s){example}")
+        })
+        .collect();
+    if violations.len() > SHOWN_VIOLATIONS {
+        descriptions.push(format!(
+            "...and {} more disallowed opcode(s)",
+            violations.len().saturating_sub(SHOWN_VIOLATIONS)
+        ));
+    }
+
+    anyhow::bail!(
+        "Module has {total} instruction(s) with {} opcode(s) that aren't allowed by '{}':\n  {}",
+        violations.len(),
+        allowed_path.display(),
+        descriptions.join("\n  ")
+    )
+}
@@ -0,0 +1,51 @@
+//! Write files atomically, so that other processes never see a partially written file.
+
+use std::path::{Path, PathBuf};
+
+/// The temporary file that's written to before being renamed into place. It's in the same
+/// directory as the destination so that the rename doesn't cross filesystems.
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    temporary.into()
+}
+
+/// Write the contents to a temporary file and then rename it over the destination.
+pub fn write(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let temporary = temporary_path(path);
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Copy the file to a temporary file and then rename it over the destination.
+pub fn copy(from: &Path, to: &Path) -> anyhow::Result<()> {
+    let temporary = temporary_path(to);
+    std::fs::copy(from, &temporary)?;
+    std::fs::rename(&temporary, to)?;
+    Ok(())
+}
+
+/// Point a link at the target, replacing any existing link, by creating it under a temporary
+/// name and renaming it into place. The target must be in the same directory as the link, so it's
+/// linked to by filename and the link keeps working if the directory moves. Windows can't
+/// generally create symlinks without extra privileges, so there the target is copied instead.
+pub fn link(target: &Path, link_path: &Path) -> anyhow::Result<()> {
+    let temporary = temporary_path(link_path);
+    if temporary.symlink_metadata().is_ok() {
+        std::fs::remove_file(&temporary)?;
+    }
+
+    #[cfg(unix)]
+    {
+        let Some(filename) = target.file_name() else {
+            anyhow::bail!("Can't link to '{}', it has no filename", target.display());
+        };
+        std::os::unix::fs::symlink(filename, &temporary)?;
+    }
+    #[cfg(not(unix))]
+    std::fs::copy(target, &temporary)?;
+
+    std::fs::rename(&temporary, link_path)?;
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Record every compile in an SQLite database, as a data source for tracking shader build health
+//! over time.
+
+use std::path::Path;
+
+/// Creates the table that compiles are recorded in, if it doesn't exist yet.
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS compiles (
+    id INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    crate TEXT NOT NULL,
+    target TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    duration_ms INTEGER,
+    bytes INTEGER,
+    warnings INTEGER NOT NULL
+)";
+
+/// A single compile.
+pub struct Compile<'compile> {
+    /// The shader crate's directory name.
+    pub crate_name: &'compile str,
+    /// The rust-gpu compile target.
+    pub target: &'compile str,
+    /// Whether the compile, and everything after it, like validation, succeeded.
+    pub success: bool,
+    /// How long the compile took, if it's known.
+    pub duration: Option<core::time::Duration>,
+    /// The total size of the compiled modules, if they could be read.
+    pub bytes: Option<u64>,
+    /// The number of warnings logged while handling the compile.
+    pub warnings: usize,
+}
+
+/// Add the compile to the database, creating the database if needed.
+pub fn record(path: &Path, compile: &Compile<'_>) -> anyhow::Result<()> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let duration_ms = compile
+        .duration
+        .map(|duration| i64::try_from(duration.as_millis()))
+        .transpose()?;
+    let bytes = compile.bytes.map(i64::try_from).transpose()?;
+
+    let connection = rusqlite::Connection::open(path)?;
+    connection.execute_batch(CREATE_TABLE)?;
+    connection.execute(
+        "INSERT INTO compiles (timestamp, crate, target, success, duration_ms, bytes, warnings)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            i64::try_from(timestamp)?,
+            compile.crate_name,
+            compile.target,
+            compile.success,
+            duration_ms,
+            bytes,
+            i64::try_from(compile.warnings)?,
+        ],
+    )?;
+
+    Ok(())
+}
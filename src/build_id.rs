@@ -0,0 +1,48 @@
+//! Inject an identifier for the build into the shader crate's compile environment, so that
+//! shaders can embed it with `env!("RUST_GPU_BUILD_ID")` and be identified at runtime.
+
+use std::path::Path;
+
+/// The environment variable that shader crates can read the build ID from.
+pub const BUILD_ID_VAR: &str = "RUST_GPU_BUILD_ID";
+
+/// The short hash of the git commit that the crate is at, with a `-dirty` suffix if there are
+/// uncommitted changes.
+fn git_hash(crate_path: &Path) -> Option<String> {
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(crate_path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+
+    let hash = git(&["rev-parse", "--short", "HEAD"])?;
+    let is_dirty = git(&["status", "--porcelain"]).is_some_and(|status| !status.is_empty());
+    Some(if is_dirty {
+        format!("{hash}-dirty")
+    } else {
+        hash
+    })
+}
+
+/// Set the build ID for compiles: the given ID, or an already set `RUST_GPU_BUILD_ID`, or the
+/// crate's git hash, or, outside of git, the current Unix timestamp.
+pub fn set(crate_path: &Path, maybe_id: Option<&str>) {
+    let id = maybe_id
+        .map(ToOwned::to_owned)
+        .or_else(|| std::env::var(BUILD_ID_VAR).ok())
+        .or_else(|| git_hash(crate_path))
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .to_string()
+        });
+
+    tracing::info!("Build ID: {id}");
+    std::env::set_var(BUILD_ID_VAR, id);
+}
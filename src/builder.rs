@@ -1,30 +1,113 @@
 //! Builder daemon to compile Rust shaders into SPIR-V.
 
-use core::borrow::Borrow;
 use core::str::FromStr;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
 
+use anyhow::Context as _;
 use spirv_builder::CompileResult;
 
-use crate::validate::validate;
+use crate::allowed_opcodes;
+use crate::atomic;
+use crate::build_db;
+use crate::build_id;
+use crate::bundle;
+use crate::c_header;
+use crate::cargo_profile;
+use crate::cargo_target;
+use crate::device;
+use crate::diagnostics;
+use crate::diff;
+use crate::disassemble;
+use crate::emit_command;
+use crate::entry_summary;
+use crate::execution_modes;
+use crate::explain;
+use crate::explain_error;
+use crate::generator;
+use crate::glsl;
+use crate::hashed;
+use crate::hlsl;
+use crate::hooks;
+use crate::host_deps;
+use crate::inspect;
+use crate::json_events::{self, Format};
+use crate::memory::MemoryProfiler;
+use crate::msl;
+use crate::naga_compat::{self, NagaVersion};
+use crate::notify_socket;
+use crate::optimize::{self, OptimizeLevel};
+use crate::parallel;
+use crate::portable;
+use crate::push_constants;
+use crate::reflect;
+use crate::repl;
+use crate::repro;
+use crate::resources;
+use crate::rust_toolchain;
+use crate::shutdown;
+use crate::summary;
+use crate::summary_json;
+use crate::tap;
+use crate::targets;
+use crate::temp_output;
+use crate::trace;
+use crate::validate::{
+    self, validate, TargetEnv, UnknownInstructions, ValidationFailed, ValidationOption,
+    ValidationSettings,
+};
+use crate::validate_watch;
+use crate::watch::{self, Keepalive, RateLimiter, TimeoutAction, WatchHealth, Watchdog};
+use crate::watch_events;
+use crate::wgpu_verify;
 
-/// CLI arguments
+/// Compiled modules that have been copied to the destination, keyed by their entry points with
+/// `--multimodule`, otherwise by their filenames.
+type Outputs = std::collections::BTreeMap<String, PathBuf>;
+
+/// The longest wait before re-establishing a failed watch.
+const MAX_WATCH_RESTART_BACKOFF_SECONDS: u64 = 60;
+
+/// CLI arguments. Any option can also be set in a config file, see `config.rs`.
 #[expect(
     clippy::struct_excessive_bools,
     reason = "We're just a simple CLI app, we don't need a state machine"
 )]
 #[derive(Debug, Clone, clap::Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct ShaderCLIArgs {
+    /// Standalone tools that don't compile a shader crate.
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Shader crate to compile.
-    path_to_crate: PathBuf,
+    #[arg(required_unless_present_any = [
+        "validate_watch",
+        "print_dylib_path",
+        "list_extensions",
+        "print_targets"
+    ])]
+    path_to_crate: Option<PathBuf>,
 
-    /// If set, shader module will be copied here. Otherwise shader module is copied to the root of
-    /// the shader crate at `compiled/[crate name].spv`, see logs for exact path.
+    /// If set, shader module will be copied here, or into it if it's a directory. Otherwise shader
+    /// module is copied to the root of the shader crate at `compiled/[crate name].spv`, see logs
+    /// for exact path. With `--multimodule` it's always a directory.
     output_path: Option<PathBuf>,
 
-    /// rust-gpu compile target.
-    #[arg(short, long, default_value = "spirv-unknown-spv1.3")]
+    /// rust-gpu compile target, eg "spirv-unknown-vulkan1.2".
+    #[arg(
+        short,
+        long,
+        value_parser=Self::target,
+        default_value = "spirv-unknown-spv1.3"
+    )]
     target: String,
 
     /// Treat warnings as errors during compilation.
@@ -92,20 +175,876 @@ pub struct ShaderCLIArgs {
     /// Options:
     ///   - "spirv": validates the generated SPIR-V binary
     ///   - "wgsl": cross-compiles the SPIR-V binary to WGSL, and also validates the WGSL
+    ///   - "wgpu": like "wgsl", but validates with the capabilities and limits of a default `wgpu`
+    ///     device, as `create_shader_module` would
+    ///   - "spirv-tools": validates the SPIR-V binary with the Khronos validator from
+    ///     `spirv-tools` instead of `naga`, as Vulkan drivers' validation layers would
     #[arg(long, value_parser=Self::validation, verbatim_doc_comment)]
     validate: Option<ValidationOption>,
+
+    /// Check the compiled shader against the capabilities and extensions supported by a Vulkan
+    /// device. Expects the JSON output of `vulkaninfo --json`, or `vulkaninfo --json=<GPU index>`
+    /// to choose a device.
+    #[arg(long)]
+    device_features: Option<PathBuf>,
+
+    /// Limit the number of watch-triggered compiles per minute. When the limit is reached,
+    /// compilation pauses until the rate drops again.
+    #[arg(long)]
+    max_compiles_per_minute: Option<core::num::NonZeroUsize>,
+
+    /// Strip all debug and reflection information from the copied shader module, regardless of
+    /// the `--spirv-metadata` it was compiled with.
+    #[arg(long, default_value = "false")]
+    strip: bool,
+
+    /// After every compile, overwrite this file with a JSON report of the compile's diagnostics.
+    /// Useful for editor integrations that watch files rather than parse logs.
+    #[arg(long)]
+    diagnostics_file: Option<PathBuf>,
+
+    /// Fail, rather than warn, when the compiled shader has no entry points.
+    #[arg(long, default_value = "false")]
+    require_entry_points: bool,
+
+    /// Log the peak memory used by each compile. Only supported on Linux.
+    #[arg(long, default_value = "false")]
+    profile_memory: bool,
+
+    /// Include a hash of the module's contents in its filename, eg `shader.1a2b3c4d.spv`. A
+    /// `manifest.json` next to it maps the original filename to the hashed one.
+    #[arg(long, default_value = "false")]
+    hashed_filenames: bool,
+
+    /// Warn if the shader needs validation capabilities that aren't supported by this older
+    /// version of `naga`, eg "0.19". Useful for engines pinned to an older `wgpu`.
+    #[arg(long)]
+    target_naga_version: Option<NagaVersion>,
+
+    /// Seconds to allow each compile before `--compile-timeout-action` is taken.
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// What to do when a compile exceeds `--timeout`.
+    /// Options:
+    ///   - "warn": log a warning but let the compile finish
+    ///   - "kill": abort the compile and wait for the next change. Only supported on Linux
+    #[arg(
+        long,
+        value_parser=Self::timeout_action,
+        default_value = "warn",
+        verbatim_doc_comment
+    )]
+    compile_timeout_action: TimeoutAction,
+
+    /// Write a Chrome trace of the compilation phases to this file. View it with
+    /// `chrome://tracing` or https://ui.perfetto.dev
+    #[arg(long)]
+    emit_trace: Option<PathBuf>,
+
+    /// How entry point names become filenames when compiling with `--multimodule`.
+    /// Options:
+    ///   - "raw": use the entry point name as-is
+    ///   - "sanitized": replace characters that are awkward in filenames, like `::`, with `_`
+    ///   - "hash": use a short hash of the entry point name
+    #[arg(
+        long,
+        value_parser=Self::entry_point_name_mangling,
+        default_value = "sanitized",
+        verbatim_doc_comment
+    )]
+    entry_point_name_mangling: EntryPointNameMangling,
+
+    /// Print the effective value of every setting, and whether it came from the command line, a
+    /// config file or a default, then exit.
+    #[arg(long, default_value = "false")]
+    dump_builder_config: bool,
+
+    /// Write every setting that isn't the default, from the command line and config files, to
+    /// this TOML file in the config file format, then exit. For replacing a long command line
+    /// with a project config file.
+    #[arg(long, value_name = "PATH")]
+    export_config: Option<PathBuf>,
+
+    /// Normalize the copied shader module so that semantically identical builds produce
+    /// byte-identical files, eg for golden file tests. Strips debug info and renumbers IDs.
+    #[arg(long, default_value = "false")]
+    normalize_output: bool,
+
+    /// Fail if the compiled module's `OpMemoryModel` doesn't use this addressing model.
+    /// Options:
+    ///   - "logical": only the `Logical` addressing model
+    ///   - "physical": any of the `Physical32`, `Physical64` or `PhysicalStorageBuffer64` models
+    #[arg(long, value_parser=Self::addressing_model, verbatim_doc_comment)]
+    require_addressing_model: Option<AddressingModel>,
+
+    /// Maximum number of modules to validate in parallel when compiling with `--multimodule`.
+    /// Defaults to the number of CPUs. See `--compile-jobs` for the compile itself.
+    #[arg(long)]
+    jobs: Option<core::num::NonZeroUsize>,
+
+    /// Write a `[name].spv.repro.json` manifest next to each compiled module, with the SHA-256 of
+    /// the module, the crate's sources and `Cargo.lock`, and the toolchain and tool versions.
+    #[arg(long, default_value = "false")]
+    emit_repro_manifest: bool,
+
+    /// Whether rustc's diagnostics for the shader crate are colored.
+    /// Options:
+    ///   - "auto": colored when stderr is a terminal and `NO_COLOR` isn't set
+    ///   - "always": always colored
+    ///   - "never": never colored
+    #[arg(
+        long,
+        value_parser=Self::color_diagnostics,
+        default_value = "auto",
+        verbatim_doc_comment
+    )]
+    color_diagnostics: ColorDiagnostics,
+
+    /// When the watcher has been idle for this many seconds, run a compile in the background to
+    /// keep cargo's incremental artifacts warm, so that the next edit compiles quickly.
+    #[arg(long)]
+    keepalive_compile_interval: Option<u64>,
+
+    /// Also write the compiled module as a C header, next to the `.spv`, declaring
+    /// `static const uint32_t <IDENT>[]` and its length as `<IDENT>_len`. With `--multimodule`
+    /// each entry point's header uses `<IDENT>_<entry point>`.
+    #[arg(long, value_name = "IDENT", value_parser=Self::c_identifier)]
+    emit_c_header: Option<String>,
+
+    /// Compile once, check and validate the module, then exit with a status reflecting the
+    /// result. Nothing is copied and no directories are created. Validates with "spirv" unless
+    /// `--validate` says otherwise. Useful for pre-commit hooks.
+    #[arg(long, default_value = "false")]
+    validate_only: bool,
+
+    /// Log the generator tool and version recorded in each compiled module's SPIR-V header.
+    #[arg(long, default_value = "false")]
+    report_generator: bool,
+
+    /// Set a `cfg` for conditional compilation of the shader crate, eg `--cfg fast_math` or
+    /// `--cfg quality=high`. Can be given more than once.
+    #[arg(long, value_name = "KEY[=VALUE]", value_parser=Self::cfg)]
+    cfg: Vec<String>,
+
+    /// Don't compile anything, instead watch this directory and validate every `.spv` file in it
+    /// whenever one is created or changed. For SPIR-V produced by other tools. Validates with
+    /// "spirv" unless `--validate` says otherwise.
+    #[arg(long, conflicts_with = "path_to_crate")]
+    validate_watch: Option<PathBuf>,
+
+    /// Write a shell script that reproduces the compile outside of the daemon, with the same
+    /// environment, eg `RUSTUP_TOOLCHAIN` and `RUSTFLAGS`, and the same effective settings,
+    /// including those from config files. Useful for bug reports against `rust-gpu`.
+    #[arg(long)]
+    emit_command: Option<PathBuf>,
+
+    /// Fail if the module's image and sampler resources need capabilities that it doesn't
+    /// declare, eg a storage image with an `Rg16f` format but no `StorageImageExtendedFormats`.
+    #[arg(long, default_value = "false")]
+    check_resource_capabilities: bool,
+
+    /// Print which checks and validations would run on the compiled module, and with which
+    /// settings, then exit without compiling anything.
+    #[arg(long, default_value = "false")]
+    dry_validate: bool,
+
+    /// After each compile, summarise its errors and warnings, with counts and the first few of
+    /// each. rustc's own diagnostics are printed by cargo as usual and aren't counted.
+    #[arg(long, default_value = "false")]
+    diagnostics_summary: bool,
+
+    /// How many times to re-establish the watch if it fails, eg because of inotify limits or an
+    /// unmounted volume, before exiting.
+    #[arg(long, default_value = "5")]
+    max_watch_restarts: u32,
+
+    /// Print the dynamic library search path that compiles use to find the
+    /// `rustc_codegen_spirv` backend, then exit. For debugging a backend that won't load.
+    #[arg(long, default_value = "false")]
+    print_dylib_path: bool,
+
+    /// Record every compile in this SQLite database, creating it if needed. Each row has the
+    /// timestamp, crate, target, success, duration, output size and warning count.
+    #[arg(long)]
+    db: Option<PathBuf>,
+
+    /// Fail if the module uses any SPIR-V instruction that isn't listed in this file, eg for
+    /// runtimes that only support a subset of SPIR-V. The file has one opcode per line, eg
+    /// `OpFAdd`, and `#` comments. Module-level instructions, like `OpCapability`, need listing
+    /// too.
+    #[arg(long)]
+    allowed_opcodes: Option<PathBuf>,
+
+    /// The build identifier that shaders can embed with `env!("RUST_GPU_BUILD_ID")`. Defaults to
+    /// the shader crate's git commit hash, or the time the daemon started outside of git.
+    #[arg(long)]
+    build_id: Option<String>,
+
+    /// Append every filesystem event in the shader crate to this file, as a line of JSON with
+    /// the event's timestamp, kind and paths, whether or not it triggers a compile. For
+    /// debugging why saves do or don't cause rebuilds.
+    #[arg(long)]
+    watch_events_log: Option<PathBuf>,
+
+    /// After each compile, also pack the compiled modules into this tar archive, along with a
+    /// `manifest.json` of their entry points, capabilities and extensions.
+    #[arg(long)]
+    bundle: Option<PathBuf>,
+
+    /// When validating, also check that `naga` can generate SPIR-V from the module with this
+    /// bounds checking policy, to match how an engine compiles the shader at runtime. Not
+    /// checked by default.
+    /// Options:
+    ///   - "restrict": out of bounds accesses are clamped to the last element
+    ///   - "readzero": out of bounds reads return zero and writes are skipped
+    ///   - "unchecked": accesses aren't checked
+    #[arg(long, value_parser=Self::bounds_check, verbatim_doc_comment)]
+    bounds_check: Option<naga::proc::BoundsCheckPolicy>,
+
+    /// List commonly used SPIR-V extensions, grouped by purpose, for use with `--extension`,
+    /// then exit.
+    #[arg(long, default_value = "false")]
+    list_extensions: bool,
+
+    /// When validating with "wgsl" or "wgpu", annotate the generated WGSL with comments of the
+    /// Rust file and line that each function came from. Implies `--spirv-metadata full`, and has
+    /// no effect with `--strip`.
+    #[arg(long, default_value = "false")]
+    wgsl_source_comments: bool,
+
+    /// Fail if any capability requested with `--capability` doesn't appear in the compiled
+    /// shader's `OpCapability` instructions, to keep the list of capabilities minimal.
+    #[arg(long, default_value = "false")]
+    deny_unused_capabilities: bool,
+
+    /// Run this `spirv-opt` pass over the copied shader module, eg "merge-blocks" or
+    /// "eliminate-dead-code-aggressive". Can be given many times, to run passes in order.
+    #[arg(long, value_parser=Self::opt_pass)]
+    opt_pass: Vec<String>,
+
+    /// With `--validate-only`, write a JSON summary of the whole run to this file: whether it
+    /// succeeded, how long it took, and the path, size and status of every compiled module. For
+    /// CI steps to parse.
+    #[arg(long, requires = "validate_only")]
+    summary_json: Option<PathBuf>,
+
+    /// After every compile, write a line of JSON about it to this Unix socket or named pipe. See
+    /// "Notify socket" in the README for the format.
+    #[arg(long)]
+    notify_socket: Option<PathBuf>,
+
+    /// Stop the daemon, with a non-zero exit code, when a compiled module fails validation. By
+    /// default validation failures are logged and the daemon keeps watching. This is separate
+    /// from `--deny-warnings`, which only turns compiler warnings into compile errors, after
+    /// which the daemon always keeps watching.
+    #[arg(long, default_value = "false", requires = "validate")]
+    validation_failure_fatal: bool,
+
+    /// How validation's SPIR-V parser, `naga`, treats SPIR-V that it doesn't model yet, which
+    /// newer `rust-gpu`s can emit. Uses `naga`'s default if not set.
+    /// Options:
+    ///   - "skip": parse the module anyway when it declares capabilities `naga` doesn't support
+    ///   - "error": fail validation as soon as the module declares an unsupported capability
+    /// Instructions that `naga` can't translate always fail validation.
+    #[arg(long, value_parser=Self::unknown_instructions, verbatim_doc_comment)]
+    naga_unknown_instructions: Option<UnknownInstructions>,
+
+    /// After each successful compile, point a symlink with this filename, next to the copied
+    /// module, at the module. Gives a stable path to the latest build when using
+    /// `--hashed-filenames`. On Windows the module is copied instead.
+    #[arg(long, value_name = "NAME", conflicts_with = "multimodule")]
+    output_symlink: Option<String>,
+
+    /// Compile for the widest range of devices: don't enable any `--capability` or `--extension`
+    /// that isn't supported by every Vulkan 1.1 device, and warn about any capabilities or
+    /// extensions that the compiled shader still needs.
+    #[arg(long, default_value = "false")]
+    portable: bool,
+
+    /// Compile once, copy and check the shader module, then exit with a status code reflecting
+    /// the outcome, rather than watching for changes. For CI and build scripts.
+    #[arg(long, default_value = "false", conflicts_with = "validate_only")]
+    no_watch: bool,
+
+    /// With `--no-watch` or `--validate-only`, print the outcome to stdout in the Test Anything
+    /// Protocol: a test per compiled module, named after the crate, target and any entry point.
+    #[arg(long, default_value = "false")]
+    tap: bool,
+
+    /// Load the `rustc_codegen_spirv` backend from this directory, or this library file, rather
+    /// than from the default dynamic library search path. For non-standard installs.
+    #[arg(long)]
+    codegen_backend_path: Option<PathBuf>,
+
+    /// Ignore changes for this many milliseconds after the first compile, for environments that
+    /// fire a burst of filesystem events at startup.
+    #[arg(long, value_name = "MS")]
+    startup_quiet_period: Option<u64>,
+
+    /// Fail, rather than warn, when the compiled shader has likely mistakes, like an entry point
+    /// missing an execution mode that its stage requires.
+    #[arg(long, default_value = "false")]
+    strict: bool,
+
+    /// Validate with the capabilities of the environment the shader will run in, rather than
+    /// with none, so that validation fails when the shader uses something the runtime doesn't
+    /// support. Doesn't affect the "wgpu" validation of WGSL. With "spirv-tools" validation it's
+    /// the Vulkan version to validate for, with "webgpu" validating for Vulkan 1.0. With several,
+    /// comma separated or repeated, validation runs once for each and logs which passed.
+    /// Options:
+    ///   - "vulkan1.0": push constants, clip and cull distances, sample shading, cube arrays
+    ///   - "vulkan1.1": like "vulkan1.0", plus multiview and subgroups
+    ///   - "vulkan1.2": like "vulkan1.1", plus 64-bit floats and integers and descriptor indexing
+    ///   - "webgpu": the capabilities of a default `wgpu` device
+    #[arg(
+        long,
+        value_parser=Self::target_env,
+        value_delimiter = ',',
+        verbatim_doc_comment
+    )]
+    validation_target_env: Vec<TargetEnv>,
+
+    /// Export the compilation phases as OpenTelemetry spans to this OTLP HTTP endpoint, eg
+    /// "http://localhost:4318/v1/traces".
+    #[arg(long, value_name = "URL")]
+    otlp_endpoint: Option<String>,
+
+    /// After compiling, load the shader module on a headless `wgpu` device, failing if `wgpu`
+    /// rejects it. Skipped, with a warning, if no `wgpu` adapter is available.
+    #[arg(long, default_value = "false")]
+    verify_wgpu: bool,
+
+    /// Also write the human-readable SPIR-V assembly of each copied module, to a `.spvasm` file
+    /// next to it.
+    #[arg(long, default_value = "false")]
+    disassemble: bool,
+
+    /// Optimize the copied shader module with `spirv-opt`, reporting how much smaller it got.
+    #[arg(long, default_value = "false")]
+    optimize: bool,
+
+    /// Which of `spirv-opt`'s recommended sets of passes `--optimize` uses.
+    /// Options:
+    ///   - "none": don't optimize
+    ///   - "size": make the module smaller
+    ///   - "performance": make the module faster
+    #[arg(
+        long,
+        value_parser=Self::optimize_level,
+        default_value = "performance",
+        verbatim_doc_comment
+    )]
+    optimize_level: OptimizeLevel,
+
+    /// Fail validation if it logs any warnings, eg about missing source locations for
+    /// `--wgsl-source-comments`. Separate from `--deny-warnings`, which only applies to the
+    /// compiler's warnings.
+    #[arg(long, default_value = "false", requires = "validate")]
+    fail_on_validation_warning: bool,
+
+    /// After each compile, write a JSON reflection of the compiled modules to this file, keyed by
+    /// their filenames: their global variables with binding groups and indices, and their entry
+    /// points with workgroup sizes and input and output locations.
+    #[arg(long)]
+    reflect: Option<PathBuf>,
+
+    /// Wait until there have been no changes for this many milliseconds before compiling, so
+    /// that saving several files at once only compiles once. Saving a file often changes it more
+    /// than once. Changes during a compile don't interrupt it, they trigger one more compile.
+    /// Saves that don't change any file's contents don't trigger a compile.
+    #[arg(long, value_name = "MILLIS", default_value = "100")]
+    debounce_ms: u64,
+
+    /// After the first compile, read commands from stdin that change settings, such as the
+    /// capabilities, target or debug mode, and recompile without restarting. Changing only the
+    /// validation option or target environments re-validates the last compile's modules without
+    /// recompiling. Type `help` for the commands.
+    #[arg(long, default_value = "false", conflicts_with_all = ["no_watch", "validate_only"])]
+    repl: bool,
+
+    /// How to order the words of the `--emit-c-header` array, for consumers that reinterpret its
+    /// memory as bytes.
+    /// Options:
+    ///   - "native": the module's words, for APIs that take `uint32_t` words, like Vulkan
+    ///   - "little": the array's memory is the `.spv` file's bytes on little-endian platforms
+    ///   - "big": the array's memory is the `.spv` file's bytes on big-endian platforms
+    #[arg(
+        long,
+        value_parser=Self::byte_order,
+        default_value = "native",
+        requires = "emit_c_header",
+        verbatim_doc_comment
+    )]
+    c_header_byte_order: c_header::ByteOrder,
+
+    /// Also recompile on changes to any file in the directories listed in this file, one per
+    /// line, eg external shader include directories. Only the Rust sources and Cargo files of the
+    /// shader crate and its local dependencies are watched otherwise, so this is also for files
+    /// that shaders read with `include_bytes!` and the like. Blank lines and lines starting with
+    /// `#` are ignored, and relative directories are relative to the file.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["no_watch", "validate_only"])]
+    watch_paths_from: Option<PathBuf>,
+
+    /// The directories read from `--watch-paths-from`.
+    #[arg(skip)]
+    extra_watch_paths: Vec<PathBuf>,
+
+    /// The directories of the shader crate's local dependencies, which the daemon watches too.
+    #[arg(skip)]
+    local_dependency_dirs: Vec<PathBuf>,
+
+    /// How compiles are reported.
+    /// Options:
+    ///   - "text": human readable logs on stdout
+    ///   - "json": a line of JSON on stdout for each event, with logs on stderr. Events are
+    ///     `{"event":"compile_start"}`, `{"event":"compile_success","output":"...","bytes":N}`
+    ///     for each output module and `{"event":"compile_error","message":"..."}`.
+    #[arg(
+        long,
+        value_parser=Self::format,
+        default_value = "text",
+        conflicts_with = "validate_only",
+        verbatim_doc_comment
+    )]
+    format: Format,
+
+    /// Compile with the settings of this custom Cargo profile, eg `[profile.shader-dist]`, from
+    /// the workspace's `Cargo.toml`. `spirv-builder` only builds with the `release` profile, or
+    /// `dev` with `--debug`, so the profile must inherit from that one, and its settings override
+    /// it.
+    #[arg(long, value_name = "NAME")]
+    cargo_profile: Option<String>,
+
+    /// After each compile, log a line for each entry point with its name, execution model and,
+    /// for compute shaders, its local size.
+    #[arg(long, default_value = "false")]
+    print_entry_summary: bool,
+
+    /// Only copy, check and validate the modules of these entry points. `spirv-builder` still
+    /// compiles them all, as it can't select entry points. Can be repeated.
+    #[arg(long, value_name = "NAME", requires = "multimodule")]
+    entry_point: Vec<String>,
+
+    /// Write the WGSL that "wgsl" and "wgpu" validation cross-compile to here, rather than to the
+    /// temp directory. A path without an extension is a directory, in which the WGSL is named
+    /// after the module, otherwise it's the file to write.
+    #[arg(long, value_name = "PATH")]
+    wgsl_output: Option<PathBuf>,
+
+    /// Stop the daemon, with a non-zero exit code, on the first compile that fails to compile,
+    /// copy, check or validate. For pre-commit hooks that use watch mode.
+    #[arg(long, default_value = "false")]
+    fail_fast: bool,
+
+    /// Skip validating a module that's byte-identical to the last one that passed validation with
+    /// the same settings, as often happens in watch mode when a change doesn't affect the
+    /// compiled shader.
+    #[arg(long, default_value = "false", requires = "validate")]
+    no_validate_on_unchanged: bool,
+
+    /// Turn rustc's arithmetic overflow checks "on" or "off", whatever the profile says. By
+    /// default they follow the profile: on with `--debug`, off otherwise, unless
+    /// `--cargo-profile` says differently. Applies to the shader crate's dependencies too.
+    #[arg(long, value_name = "on|off", value_parser=Self::on_off)]
+    overflow_checks: Option<bool>,
+
+    /// Also write a GLSL translation of each entry point into this directory, named after the
+    /// entry point with an extension for its stage, eg `main_fs.frag`. For debugging with desktop
+    /// GL tooling. Warns if `naga` can't parse the generated GLSL back.
+    #[arg(long, value_name = "DIR")]
+    emit_glsl: Option<PathBuf>,
+
+    /// The desktop GLSL version that `--emit-glsl` writes, eg 450 for `#version 450 core`.
+    #[arg(long, default_value = "450", requires = "emit_glsl")]
+    glsl_version: u16,
+
+    /// Also write a Metal Shading Language translation of each module into this directory, as a
+    /// `.metal` file named after the module. For reusing shaders on Apple platforms.
+    #[arg(long, value_name = "DIR")]
+    emit_msl: Option<PathBuf>,
+
+    /// The Metal language version that `--emit-msl` targets, eg "2.1".
+    #[arg(
+        long,
+        value_name = "MAJOR.MINOR",
+        value_parser=Self::msl_version,
+        default_value = "2.0",
+        requires = "emit_msl"
+    )]
+    msl_version: (u8, u8),
+
+    /// How `--disassemble` writes the assembly.
+    /// Options:
+    ///   - "raw": the module as it is, with its numeric IDs
+    ///   - "friendly": for reviewing diffs in version control, IDs are renumbered in order of use
+    ///     and named after their debug names where possible, and the volatile header is left out
+    #[arg(
+        long,
+        value_parser=Self::disasm_style,
+        default_value = "raw",
+        requires = "disassemble",
+        verbatim_doc_comment
+    )]
+    disasm_style: disassemble::Style,
+
+    /// What a relative `OUTPUT_PATH` is relative to. The absolute destination is logged at
+    /// startup.
+    /// Options:
+    ///   - "cwd": the current working directory
+    ///   - "crate": the shader crate
+    #[arg(
+        long,
+        value_parser=Self::output_relative_to,
+        default_value = "cwd",
+        verbatim_doc_comment
+    )]
+    output_relative_to: OutputRelativeTo,
+
+    /// Also write an HLSL translation of each module into this directory, as a `.hlsl` file named
+    /// after the module. For reusing shaders in DirectX engines.
+    #[arg(long, value_name = "DIR")]
+    emit_hlsl: Option<PathBuf>,
+
+    /// The shader model that `--emit-hlsl` targets, from "5.0" to "6.7".
+    #[arg(
+        long,
+        value_name = "MAJOR.MINOR",
+        value_parser=Self::hlsl_shader_model,
+        default_value = "6.0",
+        requires = "emit_hlsl"
+    )]
+    hlsl_shader_model: naga::back::hlsl::ShaderModel,
+
+    /// After an error, explain the common rust-gpu gotchas that it matches, like a missing
+    /// `#![no_std]` or a buffer layout Vulkan doesn't accept, with a link to read more.
+    #[arg(long, default_value = "false")]
+    explain_error: bool,
+
+    /// Print every rust-gpu compile target that `--target` accepts, with the SPIR-V version and
+    /// environment each is for, then exit.
+    #[arg(long, default_value = "false")]
+    print_targets: bool,
+
+    /// Copy compiled modules into a new temporary directory, whose path is logged at startup, and
+    /// remove it on exit. For CI steps that consume the modules straight away.
+    #[arg(long, default_value = "false", conflicts_with = "output_path")]
+    output_temp: bool,
+
+    /// Don't remove the `--output-temp` directory on exit.
+    #[arg(long, default_value = "false", requires = "output_temp")]
+    keep_temp: bool,
+
+    /// When validating with `naga`, log the total size and member offsets of each push-constant
+    /// block, to check them against what the host pushes.
+    /// Options:
+    ///   - "report": just log the layouts
+    ///   - "strict": also fail if there are gaps between a block's members
+    #[arg(
+        long,
+        value_parser=Self::push_constant_layout,
+        requires = "validate",
+        verbatim_doc_comment
+    )]
+    push_constant_layout: Option<push_constants::LayoutCheck>,
+
+    /// When validating with `naga`, fail if a push-constant block is bigger than this many bytes.
+    /// Vulkan guarantees 128 bytes.
+    #[arg(long, value_name = "BYTES", requires = "validate")]
+    max_push_constant_bytes: Option<u32>,
+
+    /// Also load settings from this TOML config file, overriding those from the other config
+    /// files. Flags on the command line still override it.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Run this shell command after each successful compile, once the modules have been copied.
+    /// `RUST_GPU_OUTPUT` has the paths of the copied modules, separated like `PATH`, and
+    /// `RUST_GPU_CRATE` the path of the shader crate. A failing command is logged, but doesn't
+    /// stop the daemon.
+    #[arg(long, value_name = "CMD")]
+    on_success: Option<String>,
+
+    /// Run this shell command after each compile that fails, with `RUST_GPU_CRATE` set like for
+    /// `--on-success`.
+    #[arg(long, value_name = "CMD")]
+    on_error: Option<String>,
+
+    /// Maximum number of jobs that cargo runs in parallel to compile the shader crate, by setting
+    /// `CARGO_BUILD_JOBS`. Defaults to cargo's own default, the number of CPUs. Unlike `--jobs`,
+    /// which only parallelises validation after the compile.
+    #[arg(long, value_name = "N")]
+    compile_jobs: Option<core::num::NonZeroUsize>,
+
+    /// The shader crate's cargo target to compile to SPIR-V. Defaults to the crate's library
+    /// target, which is the only kind that `spirv-builder` compiles, so tests, benches and binaries
+    /// are never compiled as shaders. Checked against `cargo metadata` before compiling.
+    #[arg(long, value_name = "NAME")]
+    cargo_target: Option<String>,
+
+    /// When validating with `naga`, log the validation capabilities that each module needs, found
+    /// by withholding each in turn, and the `--validation-target-env` that has them all. Can't be
+    /// used with `--validate spirv-tools`.
+    #[arg(long, default_value = "false", requires = "validate")]
+    suggest_validation_capabilities: bool,
+}
+
+/// Standalone tools that don't compile a shader crate.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum Command {
+    /// Compare two SPIR-V binaries and report their semantic differences: capabilities,
+    /// extensions, entry points and instruction counts. Exits non-zero if they differ.
+    Diff {
+        /// The original SPIR-V binary.
+        before: PathBuf,
+        /// The changed SPIR-V binary.
+        after: PathBuf,
+    },
+    /// Explain what a SPIR-V capability is for, which Vulkan version or extension supports it,
+    /// and which `naga` validation capability it needs.
+    Explain {
+        /// The capability, as given to `--capability`, eg "Int64".
+        capability: String,
+    },
+}
+
+impl Command {
+    /// Run the command, returning the process's exit code.
+    pub fn run(&self) -> ExitCode {
+        let result = match self {
+            Self::Diff { before, after } => diff::compare(before, after),
+            Self::Explain { capability } => explain::explain(capability).map(|()| true),
+        };
+
+        match result {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(error) => {
+                tracing::error!("{error:?}");
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+/// Addressing models that a compiled module can be required to use.
+#[derive(Clone, Copy, Debug)]
+enum AddressingModel {
+    /// The `Logical` addressing model, where pointers are abstract.
+    Logical,
+    /// Any addressing model with physical pointers.
+    Physical,
+}
+
+/// What a relative output path is relative to.
+#[derive(Clone, Copy, Debug)]
+enum OutputRelativeTo {
+    /// The current working directory.
+    Cwd,
+    /// The shader crate.
+    Crate,
+}
+
+/// When rustc should color the shader crate's diagnostics.
+#[derive(Clone, Copy, Debug)]
+enum ColorDiagnostics {
+    /// Color when stderr is a terminal, unless `NO_COLOR` is set.
+    Auto,
+    /// Always color.
+    Always,
+    /// Never color.
+    Never,
+}
+
+impl ColorDiagnostics {
+    /// The value for cargo's `CARGO_TERM_COLOR` setting. `spirv-builder` runs cargo with piped
+    /// output, so "auto" has to be resolved against our own stderr rather than left to cargo.
+    fn cargo_term_color(self) -> &'static str {
+        use std::io::IsTerminal as _;
+
+        match self {
+            Self::Always => "always",
+            Self::Never => "never",
+            Self::Auto => {
+                let is_no_color =
+                    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                if !is_no_color && std::io::stderr().is_terminal() {
+                    "always"
+                } else {
+                    "never"
+                }
+            }
+        }
+    }
 }
 
-/// Options for SPIR-V validation.
+/// How entry point names become filenames in multimodule output.
 #[derive(Clone, Copy, Debug)]
-enum ValidationOption {
-    /// Only validate the generated SPIR-V module.
-    Spriv,
-    /// Also create a WGSL version of the SPIR-V module and validate that WGSL.
-    Wgsl,
+enum EntryPointNameMangling {
+    /// Use the entry point name as-is.
+    Raw,
+    /// Replace characters that are awkward in filenames, like the `::` of module paths.
+    Sanitized,
+    /// Use a short hash of the entry point name.
+    Hash,
+}
+
+impl EntryPointNameMangling {
+    /// The output filename for an entry point's module.
+    fn filename(self, entry_point: &str) -> String {
+        format!("{}.spv", self.name(entry_point))
+    }
+
+    /// The entry point's name, mangled.
+    fn name(self, entry_point: &str) -> String {
+        match self {
+            Self::Raw => entry_point.to_owned(),
+            Self::Sanitized => entry_point
+                .chars()
+                .map(|character| {
+                    if character.is_ascii_alphanumeric() || character == '_' || character == '-' {
+                        character
+                    } else {
+                        '_'
+                    }
+                })
+                .collect(),
+            Self::Hash => format!("{:08x}", hashed::content_hash(entry_point.as_bytes())),
+        }
+    }
 }
 
 impl ShaderCLIArgs {
+    /// The standalone command to run instead of compiling, if any.
+    pub const fn subcommand(&self) -> Option<&Command> {
+        self.command.as_ref()
+    }
+
+    /// Where to write a Chrome trace of the compilation phases, if anywhere.
+    pub fn emit_trace(&self) -> Option<&Path> {
+        self.emit_trace.as_deref()
+    }
+
+    /// Where to export OpenTelemetry spans of the compilation phases, if anywhere.
+    pub fn otlp_endpoint(&self) -> Option<&str> {
+        self.otlp_endpoint.as_deref()
+    }
+
+    /// Whether to compile once, rather than starting the daemon.
+    pub const fn is_no_watch(&self) -> bool {
+        self.no_watch
+    }
+
+    /// Whether to change settings and recompile interactively, rather than starting the daemon.
+    pub const fn is_repl(&self) -> bool {
+        self.repl
+    }
+
+    /// Whether to just compile and validate once, rather than starting the daemon.
+    pub const fn is_validate_only(&self) -> bool {
+        self.validate_only
+    }
+
+    /// Whether compiles are reported as JSON events on stdout, with logs on stderr.
+    pub const fn is_json_format(&self) -> bool {
+        matches!(self.format, Format::Json)
+    }
+
+    /// Whether to just list common SPIR-V extensions.
+    pub const fn is_list_extensions(&self) -> bool {
+        self.list_extensions
+    }
+
+    /// Whether to just print the rust-gpu compile targets.
+    pub const fn is_print_targets(&self) -> bool {
+        self.print_targets
+    }
+
+    /// Whether to just print the dynamic library search path.
+    pub const fn is_print_dylib_path(&self) -> bool {
+        self.print_dylib_path
+    }
+
+    /// Whether a shader crate will be compiled, rather than just running a standalone tool.
+    pub const fn compiles(&self) -> bool {
+        self.command.is_none()
+            && !self.list_extensions
+            && !self.print_targets
+            && !self.print_dylib_path
+            && !self.dry_validate
+            && self.validate_watch.is_none()
+    }
+
+    /// Set up everything that compiling a shader crate needs, before any compile.
+    pub fn prepare(&mut self) -> anyhow::Result<()> {
+        if self.suggest_validation_capabilities
+            && self.validate == Some(ValidationOption::SpirvTools)
+        {
+            anyhow::bail!(
+                "`--suggest-validation-capabilities` needs `naga` validation, so can't be used \
+                 with `--validate spirv-tools`"
+            );
+        }
+        rust_toolchain::ensure_rust_version()?;
+        if let Some(name) = &self.cargo_target {
+            cargo_target::check(self.crate_path(), name)?;
+        }
+        self.apply_cargo_profile()?;
+        self.use_output_temp()?;
+        Ok(())
+    }
+
+    /// Copy compiled modules into a new temporary directory, if `--output-temp` is set.
+    fn use_output_temp(&mut self) -> anyhow::Result<()> {
+        if self.output_temp {
+            let directory = temp_output::create(self.keep_temp)?;
+            tracing::info!("Writing compiled modules to temporary directory {directory:?}");
+            self.output_path = Some(directory);
+        }
+
+        Ok(())
+    }
+
+    /// Apply the settings of the `--cargo-profile`, if there is one, to compiles.
+    fn apply_cargo_profile(&self) -> anyhow::Result<()> {
+        match &self.cargo_profile {
+            Some(name) => cargo_profile::apply(self.crate_path(), name, self.debug),
+            None => Ok(()),
+        }
+    }
+
+    /// Where to load the `rustc_codegen_spirv` backend from, if not the default search path.
+    pub fn codegen_backend_path(&self) -> Option<&Path> {
+        self.codegen_backend_path.as_deref()
+    }
+
+    /// Whether each compile's errors and warnings need collecting, for `--diagnostics-summary`,
+    /// `--db` or `--fail-on-validation-warning`.
+    pub const fn collects_diagnostics(&self) -> bool {
+        self.diagnostics_summary || self.db.is_some() || self.fail_on_validation_warning
+    }
+
+    /// Whether to just print the validation plan.
+    pub const fn is_dry_validate(&self) -> bool {
+        self.dry_validate
+    }
+
+    /// Whether to validate a directory of SPIR-V binaries, rather than compiling a shader crate.
+    pub const fn is_validate_watch(&self) -> bool {
+        self.validate_watch.is_some()
+    }
+
+    /// The shader crate to compile.
+    #[expect(
+        clippy::expect_used,
+        reason = "Clap requires the crate path unless a subcommand or a non-compiling flag is given"
+    )]
+    fn crate_path(&self) -> &Path {
+        self.path_to_crate
+            .as_deref()
+            .expect("No shader crate path given")
+    }
+
     /// Clap value parser for `SpirvMetadata`.
     fn spirv_metadata(metadata: &str) -> Result<spirv_builder::SpirvMetadata, clap::Error> {
         match metadata {
@@ -121,142 +1060,1571 @@ impl ShaderCLIArgs {
         match validation {
             "spirv" => Ok(ValidationOption::Spriv),
             "wgsl" => Ok(ValidationOption::Wgsl),
+            "wgpu" => Ok(ValidationOption::Wgpu),
+            "spirv-tools" => Ok(ValidationOption::SpirvTools),
             _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
         }
     }
 
-    /// Clap value parser for `Capability`.
-    fn spirv_capability(capability: &str) -> Result<spirv_builder::Capability, clap::Error> {
-        spirv_builder::Capability::from_str(capability).map_or_else(
-            |()| Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
-            Ok,
-        )
+    /// Clap value parser for timeout actions.
+    fn timeout_action(action: &str) -> Result<TimeoutAction, clap::Error> {
+        match action {
+            "warn" => Ok(TimeoutAction::Warn),
+            "kill" => Ok(TimeoutAction::Kill),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
     }
 
-    /// Create the SPIR-V builder from the given CLI args.
-    fn make_builder(&self) -> spirv_builder::SpirvBuilder {
-        let mut builder = spirv_builder::SpirvBuilder::new(&self.path_to_crate, &self.target)
-            .deny_warnings(self.deny_warnings)
-            .release(!self.debug)
-            .multimodule(self.multimodule)
-            .spirv_metadata(self.spirv_metadata)
-            .relax_struct_store(self.relax_struct_store)
-            .relax_logical_pointer(self.relax_logical_pointer)
-            .relax_block_layout(self.relax_block_layout)
-            .uniform_buffer_standard_layout(self.uniform_buffer_standard_layout)
-            .scalar_block_layout(self.scalar_block_layout)
-            .skip_block_layout(self.skip_block_layout)
-            .preserve_bindings(self.preserve_bindings)
-            .print_metadata(spirv_builder::MetadataPrintout::None);
-
-        for capability in &self.capability {
-            builder = builder.capability(*capability);
+    /// Clap value parser for entry point name mangling.
+    fn entry_point_name_mangling(mangling: &str) -> Result<EntryPointNameMangling, clap::Error> {
+        match mangling {
+            "raw" => Ok(EntryPointNameMangling::Raw),
+            "sanitized" => Ok(EntryPointNameMangling::Sanitized),
+            "hash" => Ok(EntryPointNameMangling::Hash),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
         }
+    }
 
-        for extension in &self.extension {
+    /// Clap value parser for addressing models.
+    fn addressing_model(model: &str) -> Result<AddressingModel, clap::Error> {
+        match model {
+            "logical" => Ok(AddressingModel::Logical),
+            "physical" => Ok(AddressingModel::Physical),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for what relative output paths are relative to.
+    fn output_relative_to(base: &str) -> Result<OutputRelativeTo, clap::Error> {
+        match base {
+            "cwd" => Ok(OutputRelativeTo::Cwd),
+            "crate" => Ok(OutputRelativeTo::Crate),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for disassembly styles.
+    fn disasm_style(style: &str) -> Result<disassemble::Style, clap::Error> {
+        match style {
+            "raw" => Ok(disassemble::Style::Raw),
+            "friendly" => Ok(disassemble::Style::Friendly),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for Metal language versions.
+    fn msl_version(version: &str) -> Result<(u8, u8), clap::Error> {
+        msl::parse_version(version)
+            .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::InvalidValue))
+    }
+
+    /// Clap value parser for HLSL shader models.
+    fn hlsl_shader_model(shader_model: &str) -> Result<naga::back::hlsl::ShaderModel, clap::Error> {
+        hlsl::parse_shader_model(shader_model)
+            .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::InvalidValue))
+    }
+
+    /// Clap value parser for switches that are "on" or "off".
+    fn on_off(setting: &str) -> Result<bool, clap::Error> {
+        match setting {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for how compiles are reported.
+    fn format(format: &str) -> Result<Format, clap::Error> {
+        match format {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for diagnostic coloring.
+    fn color_diagnostics(color: &str) -> Result<ColorDiagnostics, clap::Error> {
+        match color {
+            "auto" => Ok(ColorDiagnostics::Auto),
+            "always" => Ok(ColorDiagnostics::Always),
+            "never" => Ok(ColorDiagnostics::Never),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for bounds checking policies.
+    fn bounds_check(policy: &str) -> Result<naga::proc::BoundsCheckPolicy, clap::Error> {
+        match policy {
+            "restrict" => Ok(naga::proc::BoundsCheckPolicy::Restrict),
+            "readzero" => Ok(naga::proc::BoundsCheckPolicy::ReadZeroSkipWrite),
+            "unchecked" => Ok(naga::proc::BoundsCheckPolicy::Unchecked),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for how `naga` treats SPIR-V it doesn't model.
+    fn unknown_instructions(handling: &str) -> Result<UnknownInstructions, clap::Error> {
+        match handling {
+            "skip" => Ok(UnknownInstructions::Skip),
+            "error" => Ok(UnknownInstructions::Error),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for push-constant layout checks.
+    fn push_constant_layout(check: &str) -> Result<push_constants::LayoutCheck, clap::Error> {
+        match check {
+            "report" => Ok(push_constants::LayoutCheck::Report),
+            "strict" => Ok(push_constants::LayoutCheck::Strict),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for validation target environments.
+    fn target_env(target_env: &str) -> Result<TargetEnv, clap::Error> {
+        match target_env {
+            "vulkan1.0" => Ok(TargetEnv::Vulkan1_0),
+            "vulkan1.1" => Ok(TargetEnv::Vulkan1_1),
+            "vulkan1.2" => Ok(TargetEnv::Vulkan1_2),
+            "webgpu" => Ok(TargetEnv::WebGpu),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for optimization levels.
+    fn optimize_level(level: &str) -> Result<OptimizeLevel, clap::Error> {
+        match level {
+            "none" => Ok(OptimizeLevel::None),
+            "size" => Ok(OptimizeLevel::Size),
+            "performance" => Ok(OptimizeLevel::Performance),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for the byte order of C header arrays.
+    fn byte_order(order: &str) -> Result<c_header::ByteOrder, clap::Error> {
+        match order {
+            "native" => Ok(c_header::ByteOrder::Native),
+            "little" => Ok(c_header::ByteOrder::Little),
+            "big" => Ok(c_header::ByteOrder::Big),
+            _ => Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        }
+    }
+
+    /// Clap value parser for C identifiers.
+    fn c_identifier(identifier: &str) -> Result<String, clap::Error> {
+        if c_header::is_identifier(identifier) {
+            Ok(identifier.to_owned())
+        } else {
+            Err(clap::Error::new(clap::error::ErrorKind::InvalidValue))
+        }
+    }
+
+    /// Clap value parser for `--cfg`, returning the cfg in the syntax rustc expects, eg
+    /// `key="value"`. `spirv-builder` splits extra rustflags on whitespace, so values can't
+    /// contain any.
+    fn cfg(cfg: &str) -> Result<String, clap::Error> {
+        let (key, maybe_value) = match cfg.split_once('=') {
+            Some((key, value)) => (key, Some(value.trim_matches('"'))),
+            None => (cfg, None),
+        };
+
+        // Cfg keys are Rust identifiers, which for our purposes are the same as C identifiers.
+        let is_key_valid = c_header::is_identifier(key);
+        let is_value_valid = maybe_value.map_or(true, |value| {
+            !value.is_empty()
+                && !value.contains(|character: char| character.is_whitespace() || character == '"')
+        });
+        if !is_key_valid || !is_value_valid {
+            return Err(clap::Error::new(clap::error::ErrorKind::InvalidValue));
+        }
+
+        Ok(maybe_value.map_or_else(|| key.to_owned(), |value| format!("{key}=\"{value}\"")))
+    }
+
+    /// Clap value parser for `--opt-pass`, accepting the passes that `optimize` knows by name.
+    fn opt_pass(name: &str) -> Result<String, clap::Error> {
+        if optimize::pass(name).is_some() {
+            Ok(name.to_owned())
+        } else {
+            Err(clap::Error::raw(
+                clap::error::ErrorKind::InvalidValue,
+                format!(
+                    "unknown spirv-opt pass `{name}`, expected one of: {}\n",
+                    optimize::pass_names().join(", ")
+                ),
+            ))
+        }
+    }
+
+    /// Clap value parser for rust-gpu compile targets.
+    fn target(target: &str) -> Result<String, clap::Error> {
+        targets::check(target).map_err(|error| {
+            clap::Error::raw(clap::error::ErrorKind::InvalidValue, format!("{error}\n"))
+        })?;
+        Ok(target.to_owned())
+    }
+
+    /// Clap value parser for `Capability`.
+    fn spirv_capability(capability: &str) -> Result<spirv_builder::Capability, clap::Error> {
+        spirv_builder::Capability::from_str(capability).map_or_else(
+            |()| Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+            Ok,
+        )
+    }
+
+    /// Create the SPIR-V builder from the given CLI args.
+    fn make_builder(&self) -> spirv_builder::SpirvBuilder {
+        // Source locations come from the `OpLine`s that only full metadata includes.
+        let spirv_metadata = if self.wgsl_source_comments {
+            spirv_builder::SpirvMetadata::Full
+        } else {
+            self.spirv_metadata
+        };
+
+        let mut builder = spirv_builder::SpirvBuilder::new(self.crate_path(), &self.target)
+            .deny_warnings(self.deny_warnings)
+            .release(!self.debug)
+            .multimodule(self.multimodule)
+            .spirv_metadata(spirv_metadata)
+            .relax_struct_store(self.relax_struct_store)
+            .relax_logical_pointer(self.relax_logical_pointer)
+            .relax_block_layout(self.relax_block_layout)
+            .uniform_buffer_standard_layout(self.uniform_buffer_standard_layout)
+            .scalar_block_layout(self.scalar_block_layout)
+            .skip_block_layout(self.skip_block_layout)
+            .preserve_bindings(self.preserve_bindings)
+            .print_metadata(spirv_builder::MetadataPrintout::None);
+
+        for capability in &self.capability {
+            if self.portable && !portable::is_portable_capability(&format!("{capability:?}")) {
+                tracing::warn!("Not enabling capability `{capability:?}`, as it isn't portable");
+                continue;
+            }
+            builder = builder.capability(*capability);
+        }
+
+        for extension in &self.extension {
+            if self.portable {
+                tracing::warn!("Not enabling extension `{extension}`, as it isn't portable");
+                continue;
+            }
             builder = builder.extension(extension);
         }
 
         builder
     }
 
-    /// Starts watching a shader directory and compiles on changes
-    #[expect(clippy::expect_used, reason = "We can panic at startup")]
-    pub fn start_shader_daemon(&self) {
+    /// Compile the shader crate, taking `--compile-timeout-action` if it takes longer than
+    /// `--timeout`.
+    fn build(&self) -> Result<CompileResult, spirv_builder::SpirvBuilderError> {
+        let _watchdog = self.timeout.map(|seconds| {
+            Watchdog::start(
+                core::time::Duration::from_secs(seconds),
+                self.compile_timeout_action,
+            )
+        });
+        self.make_builder().build()
+    }
+
+    /// Configure the cargo that `spirv-builder` runs as a child process, which inherits our
+    /// environment.
+    fn configure_cargo(&self) {
+        std::env::set_var(
+            "CARGO_TERM_COLOR",
+            self.color_diagnostics.cargo_term_color(),
+        );
+        if let Some(jobs) = self.compile_jobs {
+            std::env::set_var("CARGO_BUILD_JOBS", jobs.to_string());
+        }
+
+        let mut extra_rustflags: Vec<String> =
+            self.cfg.iter().map(|cfg| format!("--cfg {cfg}")).collect();
+        if let Some(overflow_checks) = self.overflow_checks {
+            let setting = if overflow_checks { "on" } else { "off" };
+            extra_rustflags.push(format!("-C overflow-checks={setting}"));
+        }
+
+        // `spirv-builder` replaces `RUSTFLAGS` with its own, but appends `RUSTGPU_RUSTFLAGS`.
+        if !extra_rustflags.is_empty() {
+            let mut rustflags: Vec<String> = std::env::var("RUSTGPU_RUSTFLAGS")
+                .into_iter()
+                .filter(|existing| !existing.trim().is_empty())
+                .collect();
+            rustflags.extend(extra_rustflags);
+            std::env::set_var("RUSTGPU_RUSTFLAGS", rustflags.join(" "));
+        }
+
+        build_id::set(self.crate_path(), self.build_id.as_deref());
+
+        // Now that the environment is set up, it can be captured.
+        if let Some(path) = &self.emit_command {
+            if let Err(error) = emit_command::write(path) {
+                tracing::error!("Couldn't write compile command: {error:?}");
+            }
+        }
+    }
+
+    /// Compile once and check the module where `spirv-builder` left it, without copying it
+    /// anywhere. Returns the process's exit code.
+    pub fn validate_only(&self) -> ExitCode {
+        tracing::info!("Compiling and validating");
+        self.configure_cargo();
+
+        let compile_started = std::time::Instant::now();
+        let (maybe_compile_result, result) = match self.build() {
+            Ok(compile_result) => {
+                let checked = self.check_compile_result(&compile_result);
+                (Some(compile_result), checked)
+            }
+            Err(error) => (None, Err(anyhow::anyhow!("Compile failed: {error:?}"))),
+        };
+        let duration = compile_started.elapsed();
+        trace::flush();
+
+        if let Some(summary_path) = &self.summary_json {
+            let summary = summary_json::Summary {
+                crate_path: self.crate_path(),
+                target: &self.target,
+                duration,
+                compile_result: maybe_compile_result.as_ref(),
+                result: &result,
+            };
+            if let Err(error) = summary_json::write(summary_path, &summary) {
+                tracing::error!("Couldn't write summary JSON: {error:?}");
+            }
+        }
+
+        if let Some(diagnostics_file) = &self.diagnostics_file {
+            if let Err(error) = diagnostics::write(diagnostics_file, &result) {
+                tracing::error!("Couldn't write diagnostics file: {error:?}");
+            }
+        }
+
+        if self.tap {
+            self.report_tap(maybe_compile_result.as_ref(), result.is_ok());
+        }
+
+        let exit_code = match result {
+            Ok(()) => {
+                tracing::info!("✅ Compiled and validated");
+                ExitCode::SUCCESS
+            }
+            Err(error) => {
+                tracing::error!("{error:?}");
+                self.log_error_explanation(&format!("{error:?}"));
+                ExitCode::FAILURE
+            }
+        };
+
+        if self.diagnostics_summary {
+            summary::take().report();
+        }
+
+        exit_code
+    }
+
+    /// Print the checks and validations that would run on compiled modules. Returns the
+    /// process's exit code.
+    #[expect(clippy::print_stdout, reason = "The plan is the flag's output")]
+    pub fn dry_validate(&self) -> ExitCode {
+        println!("Validation plan:");
+        for (number, step) in self.validation_plan().iter().enumerate() {
+            println!("  {}. {step}", number.saturating_add(1));
+        }
+
+        ExitCode::SUCCESS
+    }
+
+    /// Describe, in order, the checks and validations that would run on compiled modules.
+    fn validation_plan(&self) -> Vec<String> {
+        if let Some(directory) = &self.validate_watch {
+            let validation = self.validate.unwrap_or(ValidationOption::Spriv);
+            return validate::plan(&self.validation_settings(validation))
+                .into_iter()
+                .map(|step| {
+                    format!(
+                        "for each `.spv` that changes in '{}': {step}",
+                        directory.display()
+                    )
+                })
+                .collect();
+        }
+
+        let maybe_validation = if self.validate_only {
+            Some(self.validate.unwrap_or(ValidationOption::Spriv))
+        } else {
+            self.validate
+        };
+        let action = if self.require_entry_points {
+            "fail"
+        } else {
+            "warn"
+        };
+        let mut checks = vec![format!("{action} if the module has no entry points")];
+        if let Some(required) = self.require_addressing_model {
+            checks.push(format!(
+                "fail unless the module uses the {required:?} addressing model"
+            ));
+        }
+
+        let mut later_checks = Vec::new();
+        if self.check_resource_capabilities {
+            later_checks
+                .push("fail if image resources need capabilities that aren't declared".to_owned());
+        }
+        if let Some(allowed_opcodes_path) = &self.allowed_opcodes {
+            later_checks.push(format!(
+                "fail if any opcode isn't listed in '{}'",
+                allowed_opcodes_path.display()
+            ));
+        }
+        if let Some(naga_version) = self.target_naga_version {
+            later_checks.push(format!(
+                "warn about capabilities that naga {naga_version} doesn't support"
+            ));
+        }
+        if let Some(device_features) = &self.device_features {
+            later_checks.push(format!(
+                "fail if capabilities or extensions aren't supported by the device in '{}'",
+                device_features.display()
+            ));
+        }
+
+        let mut steps = Vec::new();
+        if self.multimodule {
+            steps.push(format!(
+                "fail if entry points' filenames collide with {:?} mangling",
+                self.entry_point_name_mangling
+            ));
+            steps.extend(
+                checks
+                    .into_iter()
+                    .chain(later_checks)
+                    .map(|step| format!("for each entry point: {step}")),
+            );
+            let jobs = self.jobs.unwrap_or_else(parallel::default_jobs);
+            if let Some(validation) = maybe_validation {
+                steps.extend(
+                    validate::plan(&self.validation_settings(validation))
+                        .into_iter()
+                        .map(|step| format!("for each entry point, {jobs} at a time: {step}")),
+                );
+            }
+        } else {
+            steps.extend(checks);
+            if let Some(validation) = maybe_validation {
+                steps.extend(validate::plan(&self.validation_settings(validation)));
+            }
+            steps.extend(later_checks);
+        }
+
+        if maybe_validation.is_none() {
+            steps.push("skip SPIR-V validation, as `--validate` isn't set".to_owned());
+        }
+
+        steps
+    }
+
+    /// Validate the SPIR-V binaries in the `--validate-watch` directory whenever they change.
+    /// Returns the process's exit code, but only if the watcher fails.
+    pub fn validate_watch(&self) -> ExitCode {
+        let Some(directory) = &self.validate_watch else {
+            return ExitCode::FAILURE;
+        };
+        let validation = self.validate.unwrap_or(ValidationOption::Spriv);
+
+        if let Err(error) = validate_watch::run(directory, &self.validation_settings(validation)) {
+            tracing::error!("{error:?}");
+        }
+        ExitCode::FAILURE
+    }
+
+    /// Run every check and validation on a compile result, without copying anything.
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn check_compile_result(&self, compile_result: &CompileResult) -> anyhow::Result<()> {
+        let validation = self.validate.unwrap_or(ValidationOption::Spriv);
+        #[expect(
+            clippy::pattern_type_mismatch,
+            reason = "`single` is a value but `&compile_result.module` is a ref?"
+        )]
+        match &compile_result.module {
+            spirv_builder::ModuleResult::SingleModule(single) => {
+                self.check_compiled_module(single)?;
+                if self.deny_unused_capabilities {
+                    self.check_unused_capabilities(&[single.as_path()])?;
+                }
+                self.check_module(single, Some(validation))?;
+            }
+            spirv_builder::ModuleResult::MultiModule(all_modules) => {
+                let multi = self.select_entry_points(all_modules);
+                self.check_filename_collisions(&multi)?;
+                if self.deny_unused_capabilities {
+                    let modules: Vec<&Path> = multi.values().map(PathBuf::as_path).collect();
+                    self.check_unused_capabilities(&modules)?;
+                }
+                for module in multi.values() {
+                    self.check_compiled_module(module)?;
+                    self.check_module(module, None)?;
+                }
+                self.validate_modules(&multi, validation)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compile once, then copy and check the modules as the daemon would, without watching for
+    /// changes. Returns the process's exit code.
+    pub fn compile_once(&self) -> ExitCode {
+        tracing::info!("Compiling once");
+        self.check_destination();
+        self.configure_cargo();
+
+        let compile_started = std::time::Instant::now();
+        let maybe_compile_result = self.compile();
+        let is_success = maybe_compile_result.as_ref().is_some_and(|compile_result| {
+            let (is_success, _copied) =
+                self.report_compile_result(compile_result, compile_started.elapsed());
+            is_success
+        });
+
+        if self.tap {
+            self.report_tap(maybe_compile_result.as_ref(), is_success);
+        }
+
+        if is_success {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
+    }
+
+    /// Compile once, then read commands from stdin that change the settings and recompile.
+    pub fn repl(&self) -> ExitCode {
+        tracing::info!("Starting REPL");
+        self.check_destination();
+        self.configure_cargo();
+
+        let mut args = self.clone();
+        let mut last_copied = args.repl_compile();
+        tracing::info!("{}", repl::HELP);
+
+        for maybe_line in std::io::stdin().lines() {
+            let line = match maybe_line {
+                Ok(line) => line,
+                Err(error) => {
+                    tracing::error!("Couldn't read from stdin: {error:?}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let command = match repl::Command::parse(&line) {
+                None => continue,
+                Some(Ok(command)) => command,
+                Some(Err(error)) => {
+                    tracing::warn!("{error}");
+                    continue;
+                }
+            };
+
+            let is_validation_only = command.is_validation_only();
+            match command {
+                repl::Command::Capability(capability) => {
+                    if let Some(index) = args
+                        .capability
+                        .iter()
+                        .position(|enabled| *enabled == capability)
+                    {
+                        args.capability.remove(index);
+                    } else {
+                        args.capability.push(capability);
+                    }
+                }
+                repl::Command::Extension(extension) => {
+                    if let Some(index) = args
+                        .extension
+                        .iter()
+                        .position(|enabled| *enabled == extension)
+                    {
+                        args.extension.remove(index);
+                    } else {
+                        args.extension.push(extension);
+                    }
+                }
+                repl::Command::Target(target) => {
+                    if let Err(error) = targets::check(&target) {
+                        tracing::warn!("{error}");
+                        continue;
+                    }
+                    args.target = target;
+                }
+                repl::Command::Debug(debug) => args.debug = debug,
+                repl::Command::Validate(option) => {
+                    if option == "off" {
+                        args.validate = None;
+                    } else if let Ok(validation) = Self::validation(&option) {
+                        args.validate = Some(validation);
+                    } else {
+                        tracing::warn!("Unknown validation option '{option}'");
+                        continue;
+                    }
+                }
+                repl::Command::TargetEnv(envs) => {
+                    match envs.split(',').map(Self::target_env).collect() {
+                        Ok(target_envs) => args.validation_target_env = target_envs,
+                        Err(_) => {
+                            tracing::warn!("Unknown validation target environment in '{envs}'");
+                            continue;
+                        }
+                    }
+                }
+                repl::Command::Compile => (),
+                repl::Command::Settings => {
+                    tracing::info!("{}", args.repl_settings());
+                    continue;
+                }
+                repl::Command::Help => {
+                    tracing::info!("{}", repl::HELP);
+                    continue;
+                }
+                repl::Command::Quit => break,
+            }
+
+            tracing::info!("{}", args.repl_settings());
+            if is_validation_only && !last_copied.is_empty() {
+                tracing::info!("Only validation changed, re-validating the last compile's modules");
+                match args.check_outputs(&last_copied) {
+                    Ok(()) => tracing::info!("✅ Re-validated"),
+                    Err(error) => tracing::error!("{error:?}"),
+                }
+            } else {
+                last_copied = args.repl_compile();
+            }
+        }
+
+        ExitCode::SUCCESS
+    }
+
+    /// Compile and handle the result. Returns the modules that were copied, so that the REPL can
+    /// re-validate them later.
+    fn repl_compile(&self) -> Outputs {
+        let compile_started = std::time::Instant::now();
+        self.compile().map_or_else(Outputs::new, |compile_result| {
+            let (_is_success, copied) =
+                self.report_compile_result(&compile_result, compile_started.elapsed());
+            copied
+        })
+    }
+
+    /// The settings that can be changed in the REPL.
+    fn repl_settings(&self) -> String {
+        format!(
+            "Settings: target {}, {} mode, capabilities {:?}, extensions {:?}, validate {:?}, \
+             validation target environments {:?}",
+            self.target,
+            if self.debug { "debug" } else { "release" },
+            self.capability,
+            self.extension,
+            self.validate,
+            self.validation_target_env
+        )
+    }
+
+    /// Report a one-shot compile in the Test Anything Protocol.
+    fn report_tap(&self, maybe_compile_result: Option<&CompileResult>, is_success: bool) {
+        let crate_name = self
+            .crate_path()
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        tap::report(
+            &tap::test_names(&crate_name, &self.target, maybe_compile_result),
+            is_success,
+        );
+    }
+
+    /// Starts watching a shader directory and compiles on changes. If the watch fails, it's
+    /// re-established, with a backoff, up to `--max-watch-restarts` times. Only returns, with the
+    /// process's exit code, once that limit is reached.
+    pub fn start_shader_daemon(&mut self) -> ExitCode {
         tracing::info!("Starting daemon");
+        self.check_destination();
+
+        if let Some(file) = &self.watch_paths_from {
+            match watch::read_paths(file) {
+                Ok(paths) => self.extra_watch_paths = paths,
+                Err(error) => {
+                    tracing::error!("{error:?}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+
+        self.configure_cargo();
+
+        if let Err(error) = host_deps::check(self.crate_path()) {
+            tracing::warn!("Couldn't check for host-only dependencies: {error:?}");
+        }
+
+        match host_deps::local_dependency_dirs(self.crate_path()) {
+            Ok(dirs) => self.local_dependency_dirs = dirs,
+            Err(error) => {
+                tracing::warn!(
+                    "Couldn't find the shader crate's local dependencies to watch: {error:?}"
+                );
+            }
+        }
 
-        let builder = self.make_builder();
+        let _maybe_events_watcher = self.watch_events_log.as_ref().and_then(|log_path| {
+            match watch_events::start(self.crate_path(), log_path) {
+                Ok(watcher) => Some(watcher),
+                Err(error) => {
+                    tracing::error!("Couldn't log watch events: {error:?}");
+                    None
+                }
+            }
+        });
+
+        let keepalive = self
+            .keepalive_compile_interval
+            .map(|seconds| Keepalive::new(core::time::Duration::from_secs(seconds)));
+
+        // Started once the first watch is established.
+        let mut maybe_keepalive_to_start = keepalive.clone();
+        let mut maybe_watcher = None;
+        let mut restarts = 0_u32;
+        loop {
+            let health = WatchHealth::start();
+
+            // Stop the failed watch before replacing it, so that a change only compiles once.
+            drop(maybe_watcher.take());
+            let failure = match self.watch(keepalive.as_ref(), health.reporter()) {
+                Ok(watcher) => {
+                    maybe_watcher = Some(watcher);
+                    if let Some(keepalive) = maybe_keepalive_to_start.take() {
+                        keepalive.touch();
+                        let args = self.clone();
+                        keepalive.start(move || args.keepalive_compile());
+                    }
+                    health.wait_for_failure()
+                }
+                Err(error) => format!("couldn't start the file watcher: {error:?}"),
+            };
+            drop(health);
+
+            restarts = restarts.saturating_add(1);
+            if restarts > self.max_watch_restarts {
+                tracing::error!(
+                    "Watch failed ({failure}), giving up after {} restarts",
+                    self.max_watch_restarts
+                );
+                return ExitCode::FAILURE;
+            }
+
+            let backoff = core::time::Duration::from_secs(
+                2_u64
+                    .saturating_pow(restarts)
+                    .min(MAX_WATCH_RESTART_BACKOFF_SECONDS),
+            );
+            tracing::warn!(
+                "Watch failed ({failure}), re-establishing it in {}s, restart {restarts} of {}",
+                backoff.as_secs(),
+                self.max_watch_restarts
+            );
+            std::thread::sleep(backoff);
+        }
+    }
+
+    /// Compile, then watch the sources of the shader crate, its local dependencies and any extra
+    /// watch paths, compiling again once changes have stopped for `--debounce-ms`. The watch's
+    /// failures are sent to `failures`. Returns the watcher, which stops watching when dropped.
+    fn watch(
+        &self,
+        keepalive: Option<&Keepalive>,
+        failures: std::sync::mpsc::Sender<String>,
+    ) -> anyhow::Result<watch::Debounced> {
+        let memory_profiler = self.profile_memory.then(MemoryProfiler::start);
+        let compile_span = tracing::debug_span!("initial compile").entered();
+        self.compile_if_changed();
+        drop(compile_span);
+        if let Some(profiler) = &memory_profiler {
+            profiler.report();
+        }
 
-        let source = self.path_to_crate.clone();
-        let is_custom_output_path = self.output_path.is_some();
-        let destination_path = self
-            .output_path
-            .borrow()
+        let quiet_until = self.startup_quiet_period.and_then(|milliseconds| {
+            std::time::Instant::now().checked_add(core::time::Duration::from_millis(milliseconds))
+        });
+        let debounce = core::time::Duration::from_millis(self.debounce_ms);
+        let args = self.clone();
+        let watch_keepalive = keepalive.cloned();
+        let mut rate_limiter = self.max_compiles_per_minute.map(RateLimiter::new);
+        watch::debounced(&self.sources(), debounce, failures, move || {
+            if quiet_until.is_some_and(|until| std::time::Instant::now() < until) {
+                tracing::debug!("Ignoring changes during the startup quiet period");
+                return;
+            }
+
+            args.compile_if_changed();
+            if let Some(profiler) = &memory_profiler {
+                profiler.report();
+            }
+            if let Some(keepalive) = &watch_keepalive {
+                keepalive.touch();
+            }
+            if let Some(limiter) = rate_limiter.as_mut() {
+                limiter.throttle();
+            }
+        })
+    }
+
+    /// The files that compiles depend on.
+    fn sources(&self) -> watch::Sources {
+        let mut packages = vec![self.crate_path().to_path_buf()];
+        packages.extend(self.local_dependency_dirs.iter().cloned());
+        watch::Sources::new(&packages, &self.extra_watch_paths)
+    }
+
+    /// Compile, unless no watched file's contents have changed since the last successful compile,
+    /// such as when an editor saves without any edits.
+    fn compile_if_changed(&self) {
+        let hash = match self.sources().hash() {
+            Ok(hash) => hash,
+            Err(error) => {
+                tracing::debug!("Couldn't hash the watched sources: {error:?}");
+                self.compile_and_report();
+                return;
+            }
+        };
+        if watch::is_unchanged_since_compile(hash) {
+            tracing::info!("No changes, skipping");
+            return;
+        }
+
+        if self.compile_and_report() {
+            watch::record_compiled_sources(hash);
+        }
+    }
+
+    /// Compile and handle the result, logging the error if the compile fails. Returns whether the
+    /// compile succeeded.
+    fn compile_and_report(&self) -> bool {
+        let compile_started = std::time::Instant::now();
+        self.compile().is_some_and(|compile_result| {
+            let (is_success, _copied) =
+                self.report_compile_result(&compile_result, compile_started.elapsed());
+            is_success
+        })
+    }
+
+    /// Compile, logging the error if the compile fails.
+    fn compile(&self) -> Option<CompileResult> {
+        if self.is_json_format() {
+            json_events::compile_start();
+        }
+        match self.build() {
+            Ok(compile_result) => Some(compile_result),
+            Err(error) => {
+                trace::flush();
+                tracing::error!("Compile failed: {error:?}");
+                self.log_error_explanation(&format!("{error:?}"));
+                self.run_hook(false, &[]);
+                if self.is_json_format() {
+                    json_events::compile_error(&format!("Compile failed: {error:?}"));
+                }
+                if self.fail_fast {
+                    stop_daemon("--fail-fast");
+                }
+                None
+            }
+        }
+    }
+
+    /// Compile without handling the result, just to keep cargo's caches warm. The watcher
+    /// handles the results of compiles triggered by actual changes.
+    fn keepalive_compile(&self) {
+        if let Err(error) = self.build() {
+            tracing::debug!("Keepalive compile failed: {error:?}");
+        }
+    }
+
+    /// Run the `--on-success` or `--on-error` command for a compile, if there is one.
+    fn run_hook(&self, is_success: bool, outputs: &[PathBuf]) {
+        let maybe_command_line = if is_success {
+            self.on_success.as_ref()
+        } else {
+            self.on_error.as_ref()
+        };
+        if let Some(command_line) = maybe_command_line {
+            hooks::run(command_line, outputs, self.crate_path());
+        }
+    }
+
+    /// Explain the known rust-gpu gotchas in the error, if `--explain-error` is set.
+    fn log_error_explanation(&self, error: &str) {
+        if !self.explain_error {
+            return;
+        }
+        if let Some(explanation) = explain_error::explain(error) {
+            tracing::info!("{explanation}");
+        }
+    }
+
+    /// Where compiled shader modules are copied to.
+    fn destination(&self) -> PathBuf {
+        match (&self.output_path, self.output_relative_to) {
+            (None, _) => self.crate_path().join("compiled"),
+            (Some(path), OutputRelativeTo::Crate) if path.is_relative() => {
+                self.crate_path().join(path)
+            }
+            (Some(path), _) => path.clone(),
+        }
+    }
+
+    /// Warn if compiled modules would be copied into the shader crate's `target/` directory,
+    /// where they'd be unexpectedly deleted by `cargo clean`.
+    fn check_destination(&self) {
+        let target_dir = absolute_path(&self.crate_path().join("target"));
+        let destination = absolute_path(&self.destination());
+        tracing::info!("Compiled modules will be copied to {destination:?}");
+        if destination.starts_with(&target_dir) {
+            tracing::warn!(
+                "Output path {destination:?} is inside the shader crate's target directory, \
+                 so it will be deleted by `cargo clean`"
+            );
+        }
+    }
+
+    /// Handle the result of a Rust-to-SPIRV compilation, logging any errors. Returns whether the
+    /// compiled modules were copied and checked successfully, and the modules that were copied,
+    /// even if checking them failed.
+    fn report_compile_result(
+        &self,
+        compile_result: &CompileResult,
+        duration: core::time::Duration,
+    ) -> (bool, Outputs) {
+        let _shutdown_guard = shutdown::defer();
+        let mut copied = Outputs::new();
+        let result = self.handle_compile_result(compile_result, &mut copied);
+        let outputs: Vec<PathBuf> = if result.is_ok() {
+            copied.values().cloned().collect()
+        } else {
+            Vec::new()
+        };
+        trace::flush();
+        if let Err(error) = &result {
+            tracing::error!("{error:?}");
+            self.log_error_explanation(&format!("{error:?}"));
+        }
+
+        if self.is_json_format() {
+            match &result {
+                Ok(()) => json_events::compile_success(&outputs),
+                Err(error) => json_events::compile_error(&format!("{error:?}")),
+            }
+        }
+
+        if let Some(diagnostics_file) = &self.diagnostics_file {
+            if let Err(error) = diagnostics::write(diagnostics_file, &result) {
+                tracing::error!("Couldn't write diagnostics file: {error:?}");
+            }
+        }
+
+        if let Some(socket_path) = &self.notify_socket {
+            notify_socket::send(socket_path, &outputs, &result);
+        }
+
+        self.run_hook(result.is_ok(), &outputs);
+
+        let diagnostics = summary::take();
+        if self.diagnostics_summary {
+            diagnostics.report();
+        }
+
+        if let Some(db) = &self.db {
+            let crate_name = self
+                .crate_path()
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+            let compile = build_db::Compile {
+                crate_name: &crate_name,
+                target: &self.target,
+                success: result.is_ok(),
+                duration: Some(duration),
+                bytes: module_bytes(compile_result),
+                warnings: diagnostics.warnings.len(),
+            };
+            if let Err(error) = build_db::record(db, &compile) {
+                tracing::error!("Couldn't record compile in database: {error:?}");
+            }
+        }
+
+        let is_validation_failure = result
             .as_ref()
-            .map_or_else(|| source.join("compiled"), core::clone::Clone::clone);
-        let destination_string = destination_path
-            .into_os_string()
-            .into_string()
-            .expect("Couldn't parse destination path");
-        let destination_for_watcher = destination_string.clone();
-
-        let validation = self.validate;
-
-        let first_compile_result = builder
-            .watch(move |compile_result| {
-                let destination_clone = destination_for_watcher.clone();
-                Self::handle_compile_result(
-                    &compile_result,
-                    is_custom_output_path,
-                    destination_clone,
-                    validation,
+            .is_err_and(|error| error.is::<ValidationFailed>());
+        if is_validation_failure && self.validation_failure_fatal {
+            stop_daemon("--validation-failure-fatal");
+        }
+        if result.is_err() && self.fail_fast {
+            stop_daemon("--fail-fast");
+        }
+
+        (result.is_ok(), copied)
+    }
+
+    /// Catch shader crates that compile but don't define any `#[spirv(...)]` entry points.
+    fn check_entry_points(&self, module_path: &Path) -> anyhow::Result<()> {
+        let module = inspect::load(module_path)?;
+        if !inspect::entry_points(&module).is_empty() {
+            return Ok(());
+        }
+
+        let message = "Shader compiled but has no entry points, are any functions marked with `#[spirv(...)]`?";
+        if self.require_entry_points {
+            anyhow::bail!(message);
+        }
+        tracing::warn!("{message}");
+
+        Ok(())
+    }
+
+    /// Warn about entry points that are missing an execution mode their stage requires, eg
+    /// `OriginUpperLeft` for fragment shaders. Fails instead with `--strict`.
+    fn check_execution_modes(&self, module_path: &Path) -> anyhow::Result<()> {
+        let missing = execution_modes::missing(module_path)?;
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let message = format!("Missing execution modes:\n  {}", missing.join("\n  "));
+        if self.strict {
+            anyhow::bail!(message);
+        }
+        tracing::warn!("{message}");
+
+        Ok(())
+    }
+
+    /// Fail if the module doesn't use the addressing model required by `--require-addressing-model`.
+    fn check_addressing_model(module_path: &Path, required: AddressingModel) -> anyhow::Result<()> {
+        let module = inspect::load(module_path)?;
+        let Some(actual) = inspect::addressing_model(&module) else {
+            anyhow::bail!("Module has no `OpMemoryModel` instruction");
+        };
+        tracing::info!("  Addressing model: {actual:?}");
+
+        let is_logical = actual == rspirv::spirv::AddressingModel::Logical;
+        match (required, is_logical) {
+            (AddressingModel::Logical, true) | (AddressingModel::Physical, false) => Ok(()),
+            (AddressingModel::Logical, false) | (AddressingModel::Physical, true) => {
+                anyhow::bail!(
+                    "Module uses the {actual:?} addressing model, but {required:?} is required"
+                )
+            }
+        }
+    }
+
+    /// Fail if any capability requested with `--capability` isn't declared by the compiled
+    /// modules, as it's likely a mistake or left over from old code. With `--multimodule` a
+    /// capability only needs to be used by one of the modules.
+    fn check_unused_capabilities(&self, modules: &[&Path]) -> anyhow::Result<()> {
+        let mut used = std::collections::HashSet::new();
+        for module_path in modules {
+            let module = inspect::load(module_path)?;
+            used.extend(
+                inspect::capabilities(&module)
+                    .iter()
+                    .map(|capability| format!("{capability:?}")),
+            );
+        }
+
+        let unused: Vec<String> = self
+            .capability
+            .iter()
+            .map(|capability| format!("{capability:?}"))
+            .filter(|capability| !used.contains(capability))
+            .collect();
+        if !unused.is_empty() {
+            anyhow::bail!(
+                "Requested capabilities aren't used by the shader: {}",
+                unused.join(", ")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The modules of the `--entry-point`s, or all of them if none were given, warning about
+    /// those that don't exist.
+    fn select_entry_points(
+        &self,
+        modules: &std::collections::BTreeMap<String, PathBuf>,
+    ) -> std::collections::BTreeMap<String, PathBuf> {
+        if self.entry_point.is_empty() {
+            return modules.clone();
+        }
+
+        for name in &self.entry_point {
+            if !modules.contains_key(name) {
+                let available: Vec<&str> = modules.keys().map(String::as_str).collect();
+                tracing::warn!(
+                    "Entry point `{name}` doesn't exist, the entry points are: {}",
+                    available.join(", ")
                 );
+            }
+        }
+
+        modules
+            .iter()
+            .filter(|&(entry_point, _)| self.entry_point.contains(entry_point))
+            .map(|(entry_point, module)| (entry_point.clone(), module.clone()))
+            .collect()
+    }
+
+    /// Fail if several entry points would be copied to the same file, for example `a::main` and
+    /// `a_main` once sanitized, rather than letting one silently overwrite the other. Filenames
+    /// are compared case-insensitively, as they would be on macOS and Windows.
+    fn check_filename_collisions(
+        &self,
+        modules: &std::collections::BTreeMap<String, PathBuf>,
+    ) -> anyhow::Result<()> {
+        let mut entry_points_by_filename: std::collections::BTreeMap<String, Vec<&str>> =
+            std::collections::BTreeMap::new();
+        for entry_point in modules.keys() {
+            let filename = self.entry_point_name_mangling.filename(entry_point);
+            entry_points_by_filename
+                .entry(filename.to_lowercase())
+                .or_default()
+                .push(entry_point);
+        }
+
+        let collisions: Vec<String> = entry_points_by_filename
+            .iter()
+            .filter(|&(_, entry_points)| entry_points.len() > 1)
+            .map(|(filename, entry_points)| format!("{filename}: {}", entry_points.join(", ")))
+            .collect();
+        if !collisions.is_empty() {
+            anyhow::bail!(
+                "Several entry points would be written to the same file, try a different \
+                 `--entry-point-name-mangling`:\n{}",
+                collisions.join("\n")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The settings to validate with, for the given validation option.
+    fn validation_settings(&self, option: ValidationOption) -> ValidationSettings {
+        ValidationSettings {
+            option,
+            bounds_check: self.bounds_check,
+            source_comments: self.wgsl_source_comments,
+            unknown_instructions: self.naga_unknown_instructions,
+            target_envs: self.validation_target_env.clone(),
+            wgsl_output: self.wgsl_output.clone(),
+            suggest_capabilities: self.suggest_validation_capabilities,
+            push_constant_layout: self.push_constant_layout,
+            max_push_constant_bytes: self.max_push_constant_bytes,
+        }
+    }
+
+    /// Validate many modules in parallel, reporting all the failures together once every module
+    /// has been validated.
+    fn validate_modules(
+        &self,
+        modules: &std::collections::BTreeMap<String, PathBuf>,
+        validation: ValidationOption,
+    ) -> anyhow::Result<()> {
+        let modules: Vec<(&String, &PathBuf)> = modules.iter().collect();
+        let jobs = self.jobs.unwrap_or_else(parallel::default_jobs);
+
+        let settings = self.validation_settings(validation);
+
+        let results = parallel::run(&modules, jobs, |&(entry_point, module)| {
+            let _span = tracing::info_span!("validate", %entry_point).entered();
+            self.fail_on_warnings(|| self.validate_module(module, &settings))
+        });
+
+        let failures: Vec<String> = modules
+            .iter()
+            .zip(results)
+            .filter_map(|(&(entry_point, _), result)| {
+                result
+                    .err()
+                    .map(|error| format!("{entry_point}: {error:?}"))
             })
-            .expect("First compile failed");
+            .collect();
+        if !failures.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} of {} modules failed:\n{}",
+                failures.len(),
+                modules.len(),
+                failures.join("\n")
+            )
+            .context(ValidationFailed));
+        }
 
-        Self::handle_compile_result(
-            &first_compile_result,
-            is_custom_output_path,
-            destination_string,
-            validation,
-        );
+        Ok(())
     }
 
-    /// Handle the result of a Rust-to-SPIRV compilation.
+    /// Validate a module, skipping it if it's unchanged and `--no-validate-on-unchanged` is set.
+    /// Returns the warnings that validating it logged.
+    fn validate_module(
+        &self,
+        module: &Path,
+        settings: &ValidationSettings,
+    ) -> anyhow::Result<Vec<String>> {
+        if self.no_validate_on_unchanged {
+            validate::validate_if_changed(module, settings)
+        } else {
+            validate(module, settings)
+        }
+    }
+
+    /// Run a validation, failing if it returned any warnings about its module and
+    /// `--fail-on-validation-warning` is set.
+    fn fail_on_warnings(
+        &self,
+        validation: impl FnOnce() -> anyhow::Result<Vec<String>>,
+    ) -> anyhow::Result<()> {
+        let warnings = validation()?;
+        if self.fail_on_validation_warning && !warnings.is_empty() {
+            anyhow::bail!(
+                "Validation logged {} warning(s):\n  {}",
+                warnings.len(),
+                warnings.join("\n  ")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Validate the module and run any other checks on it, eg `--device-features`.
+    fn check_module(
+        &self,
+        module: &Path,
+        validation: Option<ValidationOption>,
+    ) -> anyhow::Result<()> {
+        if let Some(validation) = validation {
+            let settings = self.validation_settings(validation);
+            self.fail_on_warnings(|| self.validate_module(module, &settings))
+                .context(ValidationFailed)?;
+        }
+
+        if self.check_resource_capabilities {
+            resources::check(module)?;
+        }
+
+        if let Some(allowed_opcodes_path) = &self.allowed_opcodes {
+            allowed_opcodes::check(module, allowed_opcodes_path)?;
+        }
+
+        if let Some(naga_version) = self.target_naga_version {
+            naga_compat::check(module, naga_version)?;
+        }
+
+        if let Some(device_features_path) = &self.device_features {
+            device::check(module, device_features_path)?;
+        }
+
+        if self.portable {
+            portable::check(module)?;
+        }
+
+        if self.verify_wgpu {
+            wgpu_verify::verify(module)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the translations of the module to other shading languages that were asked for,
+    /// parsing it only once.
+    fn emit_translations(&self, module_path: &Path) -> anyhow::Result<()> {
+        if self.emit_glsl.is_none() && self.emit_msl.is_none() && self.emit_hlsl.is_none() {
+            return Ok(());
+        }
+        let (module, info) = validate::parse_for_backends(module_path)?;
+
+        if let Some(glsl_directory) = &self.emit_glsl {
+            for glsl_path in glsl::write(&module, &info, glsl_directory, self.glsl_version)? {
+                tracing::info!("  Wrote GLSL to {glsl_path:?}");
+            }
+        }
+
+        if let Some(msl_directory) = &self.emit_msl {
+            let msl_path =
+                msl::write(module_path, &module, &info, msl_directory, self.msl_version)?;
+            tracing::info!("  Wrote Metal to {msl_path:?}");
+        }
+
+        if let Some(hlsl_directory) = &self.emit_hlsl {
+            let hlsl_path = hlsl::write(
+                module_path,
+                &module,
+                &info,
+                hlsl_directory,
+                self.hlsl_shader_model,
+            )?;
+            tracing::info!("  Wrote HLSL to {hlsl_path:?}");
+        }
+
+        Ok(())
+    }
+
+    /// Run the checks that only need the module as `spirv-builder` compiled it.
+    fn check_compiled_module(&self, module_path: &Path) -> anyhow::Result<()> {
+        check_module_file(module_path)?;
+        self.check_entry_points(module_path)?;
+        self.check_execution_modes(module_path)?;
+        if let Some(required) = self.require_addressing_model {
+            Self::check_addressing_model(module_path, required)?;
+        }
+
+        Ok(())
+    }
+
+    /// Copy a compiled module to `copy_to`, post-process it, and write any other outputs that
+    /// were asked for next to it. With `--multimodule` the module's entry point is given. Returns
+    /// the module's final path, which differs from `copy_to` with `--hashed-filenames`.
+    fn output_module(
+        &self,
+        module: &Path,
+        mut copy_to: PathBuf,
+        maybe_entry_point: Option<&str>,
+    ) -> anyhow::Result<PathBuf> {
+        atomic::copy(module, &copy_to).with_context(|| match maybe_entry_point {
+            Some(entry_point) => format!("Couldn't copy {entry_point} to destination"),
+            None => "Couldn't copy shader to destination".to_owned(),
+        })?;
+
+        if self.optimize {
+            optimize::optimize(&copy_to, self.optimize_level)?;
+        }
+
+        if !self.opt_pass.is_empty() {
+            optimize::run_named_passes(&copy_to, &self.opt_pass)?;
+        }
+
+        if self.normalize_output {
+            optimize::normalize(&copy_to)?;
+        }
+
+        if self.strip {
+            optimize::strip(&copy_to)?;
+        }
+
+        if self.hashed_filenames {
+            copy_to = hashed::rename(&copy_to)?;
+        }
+
+        match maybe_entry_point {
+            Some(entry_point) => tracing::info!("✅ Compiled {entry_point} to: {copy_to:?}"),
+            None => tracing::info!("✅ Compiled to: {copy_to:?}"),
+        }
+
+        if self.report_generator {
+            generator::report(&copy_to)?;
+        }
+
+        if self.emit_repro_manifest {
+            repro::write_manifest(&copy_to, self.crate_path())?;
+        }
+
+        if let Some(identifier) = &self.emit_c_header {
+            let identifier = match maybe_entry_point {
+                Some(entry_point) => format!(
+                    "{identifier}_{}",
+                    EntryPointNameMangling::Sanitized.name(entry_point)
+                ),
+                None => identifier.clone(),
+            };
+            let header = c_header::write(&copy_to, &identifier, self.c_header_byte_order)?;
+            tracing::info!("  Wrote C header to {header:?}");
+        }
+
+        if self.disassemble {
+            let assembly = disassemble::write(&copy_to, self.disasm_style)?;
+            tracing::info!("  Wrote disassembly to {assembly:?}");
+        }
+
+        if self.print_entry_summary {
+            entry_summary::log(&copy_to)?;
+        }
+
+        self.emit_translations(&copy_to)?;
+
+        Ok(copy_to)
+    }
+
+    /// Check and validate the copied modules. With `--multimodule` they're validated together, in
+    /// parallel.
+    fn check_outputs(&self, outputs: &Outputs) -> anyhow::Result<()> {
+        if !self.multimodule {
+            for module in outputs.values() {
+                self.check_module(module, self.validate)?;
+            }
+            return Ok(());
+        }
+
+        for module in outputs.values() {
+            self.check_module(module, None)?;
+        }
+        if let Some(validation) = self.validate {
+            self.validate_modules(outputs, validation)?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle the result of a Rust-to-SPIRV compilation. The copied modules are added to
+    /// `copied` as they're copied, so that they're known even if a later check fails.
+    #[tracing::instrument(level = "debug", skip_all)]
     fn handle_compile_result(
+        &self,
         compile_result: &CompileResult,
-        is_custom_output_path: bool,
-        destination: String,
-        maybe_validation: Option<ValidationOption>,
-    ) {
-        let destination_path: PathBuf = destination.into();
+        copied: &mut Outputs,
+    ) -> anyhow::Result<()> {
+        let destination_path = self.destination();
         #[expect(
             clippy::pattern_type_mismatch,
             reason = "`single` is a value but `&compile_result.module` is a ref?"
         )]
         match &compile_result.module {
             spirv_builder::ModuleResult::SingleModule(single) => {
+                self.check_compiled_module(single)?;
+                if self.deny_unused_capabilities {
+                    self.check_unused_capabilities(&[single.as_path()])?;
+                }
+
                 let mut copy_to = destination_path.clone();
 
-                #[expect(
-                    clippy::expect_used,
-                    reason = "There's no way to continue if we can't create the destination directory"
-                )]
-                if !is_custom_output_path {
-                    std::fs::create_dir_all(copy_to)
-                        .expect("Couldn't create destination directory");
-                    let filename = single.file_name().expect("Couldn't extract filename");
+                // The module is copied into the destination if it's a directory, otherwise the
+                // destination is the module's new path.
+                if self.output_path.is_none() || destination_path.is_dir() {
+                    std::fs::create_dir_all(&destination_path)
+                        .context("Couldn't create destination directory")?;
+                    let filename = single.file_name().context("Couldn't extract filename")?;
                     copy_to = destination_path.join(filename);
                 };
 
-                #[expect(
-                    clippy::expect_used,
-                    reason = "There's no way to continue if copying fails"
-                )]
-                std::fs::copy(single, copy_to.clone())
-                    .expect("Couldn't copy shader to destination");
-
-                tracing::info!("✅ Compiled to: {copy_to:?}");
-
-                if let Some(validation) = maybe_validation {
-                    let validation_result = match validation {
-                        ValidationOption::Spriv => validate(single, false),
-                        ValidationOption::Wgsl => validate(single, true),
-                    };
-                    if let Err(error) = validation_result {
-                        tracing::error!("{error}");
+                let copy_to = self.output_module(single, copy_to, None)?;
+                let filename = copy_to
+                    .file_name()
+                    .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                copied.insert(filename, copy_to.clone());
+                self.check_outputs(copied)?;
+
+                if let Some(link_name) = &self.output_symlink {
+                    let link_path = copy_to.with_file_name(link_name);
+                    if link_path == copy_to {
+                        anyhow::bail!("`--output-symlink` can't have the same name as the module");
                     }
+                    atomic::link(&copy_to, &link_path)
+                        .with_context(|| format!("Couldn't update link {link_path:?}"))?;
+                    tracing::info!("  Linked {link_path:?} to the latest module");
                 }
             }
 
-            #[expect(clippy::unimplemented, reason = "Remove once we support multimodules")]
-            spirv_builder::ModuleResult::MultiModule(multi) => {
-                tracing::info!("✅ Compile success (multiple module files)");
-                for (key, module) in multi {
-                    tracing::info!("{key:}: {module:?}");
+            spirv_builder::ModuleResult::MultiModule(all_modules) => {
+                let multi = self.select_entry_points(all_modules);
+                self.check_filename_collisions(&multi)?;
+                if self.deny_unused_capabilities {
+                    let modules: Vec<&Path> = multi.values().map(PathBuf::as_path).collect();
+                    self.check_unused_capabilities(&modules)?;
                 }
-                unimplemented!("Multimodule support not yet implemented");
+
+                std::fs::create_dir_all(&destination_path)
+                    .context("Couldn't create destination directory")?;
+                for (entry_point, module) in &multi {
+                    self.check_compiled_module(module)?;
+                    let filename = self.entry_point_name_mangling.filename(entry_point);
+                    let copy_to = self.output_module(
+                        module,
+                        destination_path.join(filename),
+                        Some(entry_point),
+                    )?;
+                    copied.insert(entry_point.clone(), copy_to);
+                }
+                self.check_outputs(copied)?;
             }
-        };
+        }
+
+        let outputs: Vec<PathBuf> = copied.values().cloned().collect();
+        if let Some(bundle_path) = &self.bundle {
+            bundle::write(bundle_path, &outputs)?;
+        }
+
+        if let Some(reflect_path) = &self.reflect {
+            reflect::write(reflect_path, &outputs)?;
+            tracing::info!("  Wrote reflection to {reflect_path:?}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Exit with a non-zero exit code because of a failure, as the flag asked.
+fn stop_daemon(flag: &str) -> ! {
+    tracing::error!("Stopping the daemon because of `{flag}`");
+    temp_output::remove();
+    trace::flush();
+    #[expect(
+        clippy::exit,
+        reason = "This runs in the watch thread, which can't stop the daemon"
+    )]
+    std::process::exit(1);
+}
+
+/// Fail if `spirv-builder` reported a module that doesn't exist or is empty, as can happen with a
+/// degenerate build, rather than copying a broken module and reporting success.
+fn check_module_file(module_path: &Path) -> anyhow::Result<()> {
+    let metadata = std::fs::metadata(module_path)
+        .with_context(|| format!("Compiled module '{}' doesn't exist", module_path.display()))?;
+    if metadata.len() == 0 {
+        anyhow::bail!("Compiled module '{}' is empty", module_path.display());
+    }
+
+    Ok(())
+}
+
+/// The total size of the compiled modules, or `None` if any of them can't be read.
+fn module_bytes(compile_result: &CompileResult) -> Option<u64> {
+    #[expect(
+        clippy::pattern_type_mismatch,
+        reason = "`single` is a value but `&compile_result.module` is a ref?"
+    )]
+    let sizes = match &compile_result.module {
+        spirv_builder::ModuleResult::SingleModule(single) => vec![std::fs::metadata(single)],
+        spirv_builder::ModuleResult::MultiModule(multi) => {
+            multi.values().map(std::fs::metadata).collect()
+        }
+    };
+    sizes
+        .into_iter()
+        .map(|maybe_metadata| maybe_metadata.ok().map(|metadata| metadata.len()))
+        .sum()
+}
+
+/// Make a path absolute, relative to the current directory, and remove any `.` and `..`
+/// components. Unlike `std::fs::canonicalize` the path doesn't need to exist.
+fn absolute_path(path: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut normalised = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalised.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::Normal(_) => {
+                normalised.push(component);
+            }
+        }
     }
+    normalised
 }
@@ -0,0 +1,69 @@
+//! Pack compiled modules into a single tar archive, with a manifest describing them, so that an
+//! engine can load one file rather than many.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde_json::json;
+
+use crate::{atomic, inspect};
+
+/// The name of the manifest inside the archive.
+const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// Describe a module for the bundle's manifest.
+fn describe(filename: &str, module_path: &Path) -> anyhow::Result<serde_json::Value> {
+    let bytes = std::fs::metadata(module_path)?.len();
+    let module = inspect::load(module_path)?;
+    let entry_points: Vec<serde_json::Value> = inspect::entry_points(&module)
+        .into_iter()
+        .map(|(model, name)| {
+            json!({
+                "name": name,
+                "execution_model": format!("{model:?}"),
+            })
+        })
+        .collect();
+    let capabilities: Vec<String> = inspect::capabilities(&module)
+        .iter()
+        .map(|capability| format!("{capability:?}"))
+        .collect();
+
+    Ok(json!({
+        "file": filename,
+        "bytes": bytes,
+        "entry_points": entry_points,
+        "capabilities": capabilities,
+        "extensions": inspect::extensions(&module),
+    }))
+}
+
+/// Write the modules, and a `manifest.json` describing their entry points and capabilities,
+/// into a tar archive at `bundle_path`.
+pub fn write(bundle_path: &Path, module_paths: &[PathBuf]) -> anyhow::Result<()> {
+    let mut archive = tar::Builder::new(Vec::new());
+    let mut modules = Vec::new();
+
+    for module_path in module_paths {
+        let filename = module_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("Couldn't get module filename")?;
+        archive.append_path_with_name(module_path, filename)?;
+        modules.push(describe(filename, module_path)?);
+    }
+
+    let manifest = serde_json::to_string_pretty(&json!({ "modules": modules }))?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(u64::try_from(manifest.len())?);
+    header.set_mode(0o644);
+    archive.append_data(&mut header, MANIFEST_FILENAME, manifest.as_bytes())?;
+
+    atomic::write(bundle_path, &archive.into_inner()?)?;
+    tracing::info!(
+        "Bundled {} module(s) into {bundle_path:?}",
+        module_paths.len()
+    );
+
+    Ok(())
+}
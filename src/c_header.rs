@@ -0,0 +1,76 @@
+//! Write a compiled module as a C header, for C and C++ engines that embed their shaders.
+
+use core::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::{atomic, inspect};
+
+/// How many words to write on each line of the array.
+const WORDS_PER_LINE: usize = 8;
+
+/// How the words of the array are ordered, for consumers that reinterpret its memory as bytes.
+/// `.spv` files are little-endian.
+#[derive(Clone, Copy, Debug)]
+pub enum ByteOrder {
+    /// The module's words, which is what APIs that take `uint32_t` words, like Vulkan, expect on
+    /// any platform.
+    Native,
+    /// On a little-endian platform the array's memory is the `.spv` file's bytes.
+    Little,
+    /// On a big-endian platform the array's memory is the `.spv` file's bytes.
+    Big,
+}
+
+/// Whether the string can be used as a C identifier.
+pub fn is_identifier(identifier: &str) -> bool {
+    let mut characters = identifier.chars();
+    characters
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
+
+/// Write a `.h` file next to the module, declaring the module's words as
+/// `static const uint32_t <identifier>[]` and their count as `<identifier>_len`, like `xxd -i`.
+/// Returns the header's path.
+pub fn write(
+    module_path: &Path,
+    identifier: &str,
+    byte_order: ByteOrder,
+) -> anyhow::Result<PathBuf> {
+    let mut words = inspect::words(&std::fs::read(module_path)?)?;
+    if matches!(byte_order, ByteOrder::Big) {
+        for word in &mut words {
+            *word = word.swap_bytes();
+        }
+    }
+    let filename = module_path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+
+    let mut header = format!(
+        "// Generated by rust-gpu-cli from {filename}, do not edit.\n\
+         // Byte order: {byte_order:?}\n\
+         #pragma once\n\
+         \n\
+         #include <stddef.h>\n\
+         #include <stdint.h>\n\
+         \n\
+         static const uint32_t {identifier}[] = {{\n"
+    );
+    for line in words.chunks(WORDS_PER_LINE) {
+        let line: Vec<String> = line.iter().map(|word| format!("0x{word:08x}")).collect();
+        writeln!(header, "    {},", line.join(", "))?;
+    }
+    writeln!(header, "}};")?;
+    writeln!(
+        header,
+        "static const size_t {identifier}_len = {};",
+        words.len()
+    )?;
+
+    let header_path = module_path.with_extension("h");
+    atomic::write(&header_path, header.as_bytes())?;
+
+    Ok(header_path)
+}
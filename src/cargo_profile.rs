@@ -0,0 +1,124 @@
+//! Compile with the settings of a custom Cargo profile. `spirv-builder` only builds with the
+//! `dev` or `release` profile, so the custom profile's settings are applied to whichever of those
+//! is used, with Cargo's `CARGO_PROFILE_<NAME>_<KEY>` environment overrides.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+/// Profiles are only read from the workspace root's manifest, which is the closest manifest with
+/// a `[workspace]` table, or else the crate's own.
+fn root_manifest(crate_path: &Path) -> anyhow::Result<(PathBuf, toml::Table)> {
+    let crate_manifest = crate_path.join("Cargo.toml");
+    for directory in crate_path.ancestors() {
+        let manifest_path = directory.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let manifest = read_manifest(&manifest_path)?;
+        if manifest.contains_key("workspace") {
+            return Ok((manifest_path, manifest));
+        }
+    }
+
+    let manifest = read_manifest(&crate_manifest)?;
+    Ok((crate_manifest, manifest))
+}
+
+/// Parse a `Cargo.toml`.
+fn read_manifest(path: &Path) -> anyhow::Result<toml::Table> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read '{}'", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("Couldn't parse '{}'", path.display()))
+}
+
+/// The profile's settings, including those it inherits from other custom profiles, and the
+/// built-in profile it ultimately inherits from.
+fn resolve(
+    profiles: &toml::Table,
+    name: &str,
+    manifest_path: &Path,
+) -> anyhow::Result<(toml::Table, String)> {
+    let mut chain = Vec::new();
+    let mut current = name.to_owned();
+    let base = loop {
+        if current == "dev" || current == "release" {
+            break current;
+        }
+        if chain.contains(&current) {
+            anyhow::bail!("Cargo profile `{name}` inherits from itself");
+        }
+        let profile = profiles
+            .get(&current)
+            .and_then(toml::Value::as_table)
+            .with_context(|| {
+                format!(
+                    "No Cargo profile `{current}` in '{}'",
+                    manifest_path.display()
+                )
+            })?;
+        let inherits = profile
+            .get("inherits")
+            .and_then(toml::Value::as_str)
+            .with_context(|| format!("Cargo profile `{current}` doesn't say what it inherits"))?
+            .to_owned();
+        chain.push(current);
+        current = inherits;
+    };
+
+    let mut settings = toml::Table::new();
+    for profile_name in chain.iter().rev() {
+        if let Some(profile) = profiles.get(profile_name).and_then(toml::Value::as_table) {
+            for (key, value) in profile {
+                // Tables, like `build-override`, are for crates the shader crate doesn't use.
+                if key != "inherits" && !value.is_table() {
+                    settings.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    Ok((settings, base))
+}
+
+/// Apply the custom profile's settings to the `dev` profile when compiling in debug mode, or the
+/// `release` profile otherwise. Fails if the profile doesn't exist or it inherits from the other
+/// built-in profile.
+pub fn apply(crate_path: &Path, name: &str, is_debug: bool) -> anyhow::Result<()> {
+    let (manifest_path, manifest) = root_manifest(crate_path)?;
+    let profiles = manifest
+        .get("profile")
+        .and_then(toml::Value::as_table)
+        .cloned()
+        .unwrap_or_default();
+    let (settings, base) = resolve(&profiles, name, &manifest_path)?;
+
+    let used = if is_debug { "dev" } else { "release" };
+    if base != used {
+        anyhow::bail!(
+            "Cargo profile `{name}` inherits from `{base}`, but shaders are being compiled with \
+             `{used}`{}",
+            if is_debug {
+                ", remove `--debug`"
+            } else {
+                ", add `--debug`"
+            }
+        );
+    }
+
+    for (key, value) in &settings {
+        let variable = format!(
+            "CARGO_PROFILE_{}_{}",
+            used.to_uppercase(),
+            key.to_uppercase().replace('-', "_")
+        );
+        let value = match value {
+            &toml::Value::String(ref string) => string.clone(),
+            other => other.to_string(),
+        };
+        std::env::set_var(variable, value);
+    }
+    tracing::info!("Using the settings of Cargo profile `{name}` for the `{used}` profile");
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Choose which of the shader crate's cargo targets is compiled to SPIR-V. `spirv-builder` always
+//! runs `cargo build --lib`, so the crate's library is the only target that it can compile. Naming
+//! any other target, such as a test or bench harness, fails before the compile rather than
+//! partway through it.
+
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde_json::Value;
+
+use crate::host_deps;
+
+/// Target kinds that aren't libraries, and so that `spirv-builder` doesn't compile.
+const NON_LIBRARY_KINDS: &[&str] = &["bin", "test", "bench", "example", "custom-build"];
+
+/// Check that the shader crate has a cargo target called `name`, and that it's the library target
+/// that `spirv-builder` compiles.
+pub fn check(crate_path: &Path, name: &str) -> anyhow::Result<()> {
+    let metadata = host_deps::metadata(crate_path)?;
+    let root = metadata
+        .get("resolve")
+        .and_then(|resolve| resolve.get("root"))
+        .and_then(Value::as_str)
+        .context("`cargo metadata` has no root package")?;
+    let package = metadata
+        .get("packages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|package| package.get("id").and_then(Value::as_str) == Some(root))
+        .context("`cargo metadata` has no root package")?;
+    let targets: Vec<&Value> = package
+        .get("targets")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let Some(target) = targets
+        .iter()
+        .find(|target| target.get("name").and_then(Value::as_str) == Some(name))
+    else {
+        let names: Vec<&str> = targets
+            .iter()
+            .filter_map(|target| target.get("name")?.as_str())
+            .collect();
+        anyhow::bail!(
+            "Shader crate has no cargo target `{name}`, its targets are: {}",
+            names.join(", ")
+        );
+    };
+
+    let maybe_non_library = target
+        .get("kind")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .find(|kind| NON_LIBRARY_KINDS.contains(kind));
+    if let Some(kind) = maybe_non_library {
+        anyhow::bail!(
+            "Cargo target `{name}` is a {kind} target, but `spirv-builder` only compiles a \
+             crate's library target to SPIR-V"
+        );
+    }
+
+    Ok(())
+}
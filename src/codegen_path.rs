@@ -0,0 +1,70 @@
+//! The dynamic library search path, where `spirv-builder` looks for the `rustc_codegen_spirv`
+//! backend that rustc loads to compile shaders.
+
+/// The environment variable that holds the platform's dynamic library search path.
+pub const fn dylib_path_var() -> &'static str {
+    if cfg!(windows) {
+        "PATH"
+    } else if cfg!(target_os = "macos") {
+        "DYLD_FALLBACK_LIBRARY_PATH"
+    } else {
+        "LD_LIBRARY_PATH"
+    }
+}
+
+/// Put the `rustc_codegen_spirv` backend at `path` first in the dynamic library search path.
+/// `path` can be the backend library itself or the directory that contains it.
+pub fn set_codegen_backend_path(path: &std::path::Path) -> anyhow::Result<()> {
+    let filename = format!(
+        "{}rustc_codegen_spirv{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    );
+
+    let directory = if path.is_dir() {
+        if !path.join(&filename).is_file() {
+            anyhow::bail!(
+                "Codegen backend directory '{}' doesn't contain `{filename}`",
+                path.display()
+            );
+        }
+        path.to_owned()
+    } else if path.is_file() {
+        if path.file_name() != Some(filename.as_ref()) {
+            anyhow::bail!(
+                "Codegen backend '{}' isn't named `{filename}`, so it won't be found",
+                path.display()
+            );
+        }
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+            _ => ".".into(),
+        }
+    } else {
+        anyhow::bail!("Codegen backend path '{}' doesn't exist", path.display());
+    };
+
+    let name = dylib_path_var();
+    let mut directories = vec![directory];
+    directories.extend(std::env::split_paths(
+        &std::env::var_os(name).unwrap_or_default(),
+    ));
+    std::env::set_var(name, std::env::join_paths(directories)?);
+
+    Ok(())
+}
+
+/// Print the dynamic library search path, as compiles will see it, followed by each of its
+/// directories in search order.
+#[expect(
+    clippy::print_stdout,
+    reason = "Printing the path is the flag's output"
+)]
+pub fn print_dylib_path() {
+    let name = dylib_path_var();
+    let value = std::env::var_os(name).unwrap_or_default();
+    println!("{name}={}", value.to_string_lossy());
+    for directory in std::env::split_paths(&value) {
+        println!("  {}", directory.display());
+    }
+}
@@ -0,0 +1,323 @@
+//! Load CLI arguments from layered TOML config files. Each layer overrides the previous one:
+//!   1. Global: `rust-gpu-cli.toml` in the user's config directory, eg `~/.config`.
+//!   2. Project: `rust-gpu-cli.toml` in the shader crate.
+//!   3. Local: `rust-gpu-cli.local.toml` in the shader crate, for personal, uncommitted settings.
+//!   4. The file given with `--config`.
+//!   5. Flags given on the command line.
+//!
+//! Config keys are the names of the long CLI options, eg `target = "spirv-unknown-vulkan1.2"`,
+//! `capability = ["Int8", "Int64"]` or `debug = true`. An option given on the command line replaces
+//! the config's value rather than adding to it, and a boolean flag that a config file turns on can
+//! be turned off with its hidden `--no-` form, eg `--no-debug`.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::Context as _;
+use clap::{CommandFactory as _, FromArgMatches as _};
+
+use crate::atomic;
+use crate::builder::ShaderCLIArgs;
+
+/// The filename of global and project config files.
+const CONFIG_FILENAME: &str = "rust-gpu-cli.toml";
+
+/// The filename of local config files.
+const LOCAL_CONFIG_FILENAME: &str = "rust-gpu-cli.local.toml";
+
+/// A config file and the settings it provides.
+struct Layer {
+    /// Description of the layer, for `--dump-builder-config`.
+    name: &'static str,
+    /// Path to the config file.
+    path: PathBuf,
+    /// The settings in the config file.
+    table: toml::Table,
+}
+
+/// The arguments that were actually parsed: those from config files followed by those from the
+/// command line.
+static EFFECTIVE_ARGS: OnceLock<Vec<OsString>> = OnceLock::new();
+
+/// The arguments that were actually parsed, including the program name. Empty until `parse()`.
+pub fn effective_args() -> &'static [OsString] {
+    EFFECTIVE_ARGS.get().map_or(&[], Vec::as_slice)
+}
+
+/// What parsing the CLI arguments did.
+pub enum Parsed {
+    /// The arguments to run with.
+    Args(Box<ShaderCLIArgs>),
+    /// A flag that's handled while loading config, like `--dump-builder-config` or
+    /// `--export-config`, has done
+    /// everything, so there's nothing to run.
+    Done,
+}
+
+/// Parse the CLI arguments, filling in any that aren't given from config files.
+pub fn parse() -> anyhow::Result<Parsed> {
+    let cli_args: Vec<OsString> = std::env::args_os().collect();
+    let command = command();
+    let cli_matches = command.clone().get_matches_from(&cli_args);
+
+    let Some(path_to_crate) = cli_matches.get_one::<PathBuf>("path_to_crate") else {
+        drop(EFFECTIVE_ARGS.set(cli_args));
+        // Subcommands and flags like `--validate-watch` don't compile a crate and so don't need
+        // config.
+        return Ok(Parsed::Args(Box::new(ShaderCLIArgs::from_arg_matches(
+            &cli_matches,
+        )?)));
+    };
+
+    let layers = load_layers(path_to_crate, cli_matches.get_one::<PathBuf>("config"))?;
+    let mut merged = toml::Table::new();
+    for layer in &layers {
+        merged.extend(layer.table.clone());
+    }
+
+    let mut args = cli_args.iter().take(1).cloned().collect::<Vec<_>>();
+    args.extend(config_to_args(&merged, &command, &cli_matches)?);
+    args.extend(cli_args.iter().skip(1).cloned());
+    let matches = command.clone().get_matches_from(&args);
+    drop(EFFECTIVE_ARGS.set(args));
+
+    if matches.get_flag("dump_builder_config") {
+        dump(&command, &matches, &cli_matches, &layers);
+        return Ok(Parsed::Done);
+    }
+
+    if let Some(export_path) = matches.get_one::<PathBuf>("export_config") {
+        export(&command, &matches, export_path)?;
+        return Ok(Parsed::Done);
+    }
+
+    Ok(Parsed::Args(Box::new(ShaderCLIArgs::from_arg_matches(
+        &matches,
+    )?)))
+}
+
+/// The CLI's command, with a hidden `--no-<flag>` for every boolean flag. As the command line comes
+/// after the config's arguments, it can turn off a flag that a config file turns on.
+fn command() -> clap::Command {
+    let command = ShaderCLIArgs::command().args_override_self(true);
+    let negations: Vec<clap::Arg> = command
+        .get_arguments()
+        .filter(|arg| matches!(arg.get_action(), clap::ArgAction::SetTrue))
+        .filter_map(|arg| {
+            let id = format!("no_{}", arg.get_id());
+            let flag = format!("no-{}", arg.get_long()?);
+            let is_taken = command.get_arguments().any(|other| {
+                other.get_id() == id.as_str() || other.get_long() == Some(flag.as_str())
+            });
+            (!is_taken).then(|| {
+                clap::Arg::new(id)
+                    .long(flag)
+                    .action(clap::ArgAction::SetTrue)
+                    .overrides_with(arg.get_id().clone())
+                    .hide(true)
+            })
+        })
+        .collect();
+    command.args(negations)
+}
+
+/// Whether the command line set an argument, or turned it off with its `--no-` form.
+fn is_from_command_line(command: &clap::Command, cli_matches: &clap::ArgMatches, id: &str) -> bool {
+    let negation = format!("no_{id}");
+    let is_negated = command
+        .get_arguments()
+        .any(|arg| arg.is_hide_set() && arg.get_id() == negation.as_str())
+        && cli_matches.value_source(&negation) == Some(clap::parser::ValueSource::CommandLine);
+    is_negated || cli_matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine)
+}
+
+/// The path of the global config file, if there's a config directory.
+fn global_config_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .map(|directory| directory.join(CONFIG_FILENAME))
+}
+
+/// Load whichever config files exist, lowest priority first. The `--config` file has to exist.
+fn load_layers(
+    path_to_crate: &Path,
+    maybe_explicit: Option<&PathBuf>,
+) -> anyhow::Result<Vec<Layer>> {
+    if let Some(explicit) = maybe_explicit.filter(|path| !path.is_file()) {
+        anyhow::bail!("Config file '{}' doesn't exist", explicit.display());
+    }
+
+    let candidates = [
+        ("global config", global_config_path()),
+        ("project config", Some(path_to_crate.join(CONFIG_FILENAME))),
+        (
+            "local config",
+            Some(path_to_crate.join(LOCAL_CONFIG_FILENAME)),
+        ),
+        ("--config file", maybe_explicit.cloned()),
+    ];
+
+    let mut layers = Vec::new();
+    for (name, maybe_path) in candidates {
+        let Some(path) = maybe_path.filter(|path| path.exists()) else {
+            continue;
+        };
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read config file '{}'", path.display()))?;
+        let table = contents
+            .parse::<toml::Table>()
+            .with_context(|| format!("Couldn't parse config file '{}'", path.display()))?;
+        layers.push(Layer { name, path, table });
+    }
+
+    Ok(layers)
+}
+
+/// Convert config settings into the equivalent CLI arguments. Settings that are given on the
+/// command line are left out, so that the command line replaces them rather than adding to them.
+fn config_to_args(
+    config: &toml::Table,
+    command: &clap::Command,
+    cli_matches: &clap::ArgMatches,
+) -> anyhow::Result<Vec<OsString>> {
+    let mut args = Vec::new();
+    for (key, value) in config {
+        let flag = key.replace('_', "-");
+        if flag == "config" {
+            anyhow::bail!("Config files can't set `config`");
+        }
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(flag.as_str()))
+        else {
+            anyhow::bail!("Unknown config setting `{key}`");
+        };
+        if is_from_command_line(command, cli_matches, arg.get_id().as_str()) {
+            continue;
+        }
+
+        let values = match value {
+            toml::Value::Array(items) => items.iter().collect(),
+            toml::Value::String(_)
+            | toml::Value::Integer(_)
+            | toml::Value::Float(_)
+            | toml::Value::Boolean(_)
+            | toml::Value::Datetime(_)
+            | toml::Value::Table(_) => vec![value],
+        };
+
+        for item in values {
+            match item {
+                toml::Value::Boolean(true) => args.push(format!("--{flag}").into()),
+                toml::Value::Boolean(false) => (),
+                toml::Value::String(string) => {
+                    args.push(format!("--{flag}").into());
+                    args.push(string.into());
+                }
+                toml::Value::Integer(_) | toml::Value::Float(_) => {
+                    args.push(format!("--{flag}").into());
+                    args.push(item.to_string().into());
+                }
+                toml::Value::Datetime(_) | toml::Value::Array(_) | toml::Value::Table(_) => {
+                    anyhow::bail!("Unsupported value for config setting `{key}`: {item}");
+                }
+            }
+        }
+    }
+
+    Ok(args)
+}
+
+/// Arguments that don't belong in a config file.
+const UNEXPORTED_ARGS: &[&str] = &["path_to_crate", "output_path", "export_config", "config"];
+
+/// Write every setting that doesn't have its default value as a config file, so that it can
+/// replace the command line.
+#[expect(
+    clippy::print_stdout,
+    reason = "Confirming the export is the flag's output"
+)]
+fn export(
+    command: &clap::Command,
+    matches: &clap::ArgMatches,
+    export_path: &Path,
+) -> anyhow::Result<()> {
+    let mut table = toml::Table::new();
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if UNEXPORTED_ARGS.contains(&id)
+            || arg.is_hide_set()
+            || matches.value_source(id) == Some(clap::parser::ValueSource::DefaultValue)
+        {
+            continue;
+        }
+        let Some(raw_values) = matches.get_raw(id) else {
+            continue;
+        };
+        let mut values: Vec<toml::Value> = raw_values
+            .map(|value| toml::Value::String(value.to_string_lossy().into_owned()))
+            .collect();
+
+        let value = if !arg.get_action().takes_values() {
+            toml::Value::Boolean(true)
+        } else if matches!(arg.get_action(), clap::ArgAction::Append) {
+            toml::Value::Array(values)
+        } else if let Some(single) = values.pop() {
+            single
+        } else {
+            continue;
+        };
+        table.insert(id.to_owned(), value);
+    }
+
+    let contents = format!(
+        "# Exported by `rust-gpu-cli --export-config`.\n{}",
+        toml::to_string(&table)?
+    );
+    atomic::write(export_path, contents.as_bytes())
+        .with_context(|| format!("Couldn't write config to '{}'", export_path.display()))?;
+    println!("Wrote config to '{}'", export_path.display());
+
+    Ok(())
+}
+
+/// Print the effective value of every setting and where it came from.
+#[expect(
+    clippy::print_stdout,
+    reason = "Dumping the config is the flag's output"
+)]
+fn dump(
+    command: &clap::Command,
+    matches: &clap::ArgMatches,
+    cli_matches: &clap::ArgMatches,
+    layers: &[Layer],
+) {
+    for arg in command.get_arguments().filter(|arg| !arg.is_hide_set()) {
+        let id = arg.get_id().as_str();
+        let Some(values) = matches.get_raw(id) else {
+            continue;
+        };
+        let values: Vec<String> = values
+            .map(|value| value.to_string_lossy().into_owned())
+            .collect();
+
+        let maybe_layer = arg.get_long().and_then(|flag| {
+            let key = flag.replace('-', "_");
+            layers.iter().rev().find(|layer| {
+                layer.table.contains_key(flag) || layer.table.contains_key(key.as_str())
+            })
+        });
+        let source = if is_from_command_line(command, cli_matches, id) {
+            "command line".to_owned()
+        } else if let Some(layer) = maybe_layer {
+            format!("{} '{}'", layer.name, layer.path.display())
+        } else {
+            "default".to_owned()
+        };
+
+        println!("{id} = {} ({source})", values.join(", "));
+    }
+}
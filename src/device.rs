@@ -0,0 +1,271 @@
+//! Check a compiled SPIR-V module against the features of a specific Vulkan device.
+//! Device features are read from the JSON output of `vulkaninfo --json`.
+
+use std::collections::HashSet;
+
+use anyhow::Context;
+use serde_json::{Map, Value};
+
+use crate::inspect;
+
+/// SPIR-V capabilities and the Vulkan device feature that must be enabled to use them.
+/// See the "SPIR-V Environment" appendix of the Vulkan spec. Capabilities that aren't listed are
+/// either always supported or aren't checked.
+pub const CAPABILITY_FEATURES: &[(&str, &str)] = &[
+    ("Geometry", "geometryShader"),
+    ("Tessellation", "tessellationShader"),
+    ("Float64", "shaderFloat64"),
+    ("Int64", "shaderInt64"),
+    ("Int64Atomics", "shaderBufferInt64Atomics"),
+    ("Int16", "shaderInt16"),
+    ("Int8", "shaderInt8"),
+    ("Float16", "shaderFloat16"),
+    (
+        "TessellationPointSize",
+        "shaderTessellationAndGeometryPointSize",
+    ),
+    (
+        "GeometryPointSize",
+        "shaderTessellationAndGeometryPointSize",
+    ),
+    ("ImageGatherExtended", "shaderImageGatherExtended"),
+    ("StorageImageMultisample", "shaderStorageImageMultisample"),
+    ("ImageMSArray", "shaderStorageImageMultisample"),
+    (
+        "StorageImageExtendedFormats",
+        "shaderStorageImageExtendedFormats",
+    ),
+    (
+        "StorageImageReadWithoutFormat",
+        "shaderStorageImageReadWithoutFormat",
+    ),
+    (
+        "StorageImageWriteWithoutFormat",
+        "shaderStorageImageWriteWithoutFormat",
+    ),
+    (
+        "UniformBufferArrayDynamicIndexing",
+        "shaderUniformBufferArrayDynamicIndexing",
+    ),
+    (
+        "SampledImageArrayDynamicIndexing",
+        "shaderSampledImageArrayDynamicIndexing",
+    ),
+    (
+        "StorageBufferArrayDynamicIndexing",
+        "shaderStorageBufferArrayDynamicIndexing",
+    ),
+    (
+        "StorageImageArrayDynamicIndexing",
+        "shaderStorageImageArrayDynamicIndexing",
+    ),
+    ("ClipDistance", "shaderClipDistance"),
+    ("CullDistance", "shaderCullDistance"),
+    ("ImageCubeArray", "imageCubeArray"),
+    ("SampledCubeArray", "imageCubeArray"),
+    ("SampleRateShading", "sampleRateShading"),
+    ("InterpolationFunction", "sampleRateShading"),
+    ("SparseResidency", "shaderResourceResidency"),
+    ("MinLod", "shaderResourceMinLod"),
+    ("MultiViewport", "multiViewport"),
+    ("DrawParameters", "shaderDrawParameters"),
+    ("MultiView", "multiview"),
+    (
+        "VariablePointersStorageBuffer",
+        "variablePointersStorageBuffer",
+    ),
+    ("VariablePointers", "variablePointers"),
+    ("StorageBuffer16BitAccess", "storageBuffer16BitAccess"),
+    (
+        "UniformAndStorageBuffer16BitAccess",
+        "uniformAndStorageBuffer16BitAccess",
+    ),
+    ("StoragePushConstant16", "storagePushConstant16"),
+    ("StorageInputOutput16", "storageInputOutput16"),
+    ("StorageBuffer8BitAccess", "storageBuffer8BitAccess"),
+    (
+        "UniformAndStorageBuffer8BitAccess",
+        "uniformAndStorageBuffer8BitAccess",
+    ),
+    ("StoragePushConstant8", "storagePushConstant8"),
+    ("RuntimeDescriptorArray", "runtimeDescriptorArray"),
+    ("VulkanMemoryModel", "vulkanMemoryModel"),
+    ("PhysicalStorageBufferAddresses", "bufferDeviceAddress"),
+    ("RayTracingKHR", "rayTracingPipeline"),
+    ("RayQueryKHR", "rayQuery"),
+];
+
+/// SPIR-V extensions whose Vulkan counterpart isn't simply named by swapping `SPV_` for `VK_`.
+const EXTENSION_ALIASES: &[(&str, &str)] = &[
+    (
+        "SPV_KHR_physical_storage_buffer",
+        "VK_KHR_buffer_device_address",
+    ),
+    ("SPV_KHR_ray_tracing", "VK_KHR_ray_tracing_pipeline"),
+    (
+        "SPV_KHR_non_semantic_info",
+        "VK_KHR_shader_non_semantic_info",
+    ),
+    ("SPV_KHR_float_controls", "VK_KHR_shader_float_controls"),
+    (
+        "SPV_EXT_shader_atomic_float_add",
+        "VK_EXT_shader_atomic_float",
+    ),
+];
+
+/// The features and extensions reported by a Vulkan device.
+struct DeviceFeatures {
+    /// Device features that are enabled, eg `shaderFloat64`.
+    features: HashSet<String>,
+    /// Vulkan extensions the device supports, eg `VK_KHR_16bit_storage`.
+    extensions: HashSet<String>,
+}
+
+impl DeviceFeatures {
+    /// Read the device features from the JSON output of `vulkaninfo --json`.
+    fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read device features '{}'", path.display()))?;
+        let value: Value = serde_json::from_str(&json)
+            .with_context(|| format!("Couldn't parse device features '{}'", path.display()))?;
+
+        let section = device_section(&value).with_context(|| {
+            format!(
+                "Couldn't find a device in '{}', is it the output of `vulkaninfo --json`?",
+                path.display()
+            )
+        })?;
+        let device = Self {
+            features: features(section),
+            extensions: extensions(section),
+        };
+
+        if device.features.is_empty() && device.extensions.is_empty() {
+            anyhow::bail!(
+                "No device features found in '{}', is it the output of `vulkaninfo --json`?",
+                path.display()
+            );
+        }
+
+        Ok(device)
+    }
+}
+
+/// The device's section of the output of `vulkaninfo --json`. Newer `vulkaninfo`s write the
+/// Vulkan Profiles format, with the device under `capabilities`. Older ones write the devsim
+/// format, with the device's structs at the top level.
+fn device_section(value: &Value) -> anyhow::Result<&Map<String, Value>> {
+    let Some(capabilities) = value.get("capabilities") else {
+        return value.as_object().context("Expected a JSON object");
+    };
+    let capabilities = capabilities
+        .as_object()
+        .context("`capabilities` isn't an object")?;
+    if let Some(device) = capabilities.get("device") {
+        return device.as_object().context("`device` isn't an object");
+    }
+
+    match capabilities.values().collect::<Vec<_>>().as_slice() {
+        [device] => device.as_object().context("The device isn't an object"),
+        _ => anyhow::bail!(
+            "Expected the capabilities of one device, use `vulkaninfo --json=<GPU index>` to \
+             choose one"
+        ),
+    }
+}
+
+/// The device features that are enabled, from the device's `VkPhysicalDevice*Features*` structs.
+/// Only features that a capability needs are kept, as other structs' booleans are properties and
+/// limits rather than features.
+fn features(device: &Map<String, Value>) -> HashSet<String> {
+    let structs = device
+        .get("features")
+        .and_then(Value::as_object)
+        .unwrap_or(device);
+
+    structs
+        .iter()
+        .filter(|&(name, _)| name.starts_with("VkPhysicalDevice") && name.contains("Features"))
+        .filter_map(|(_, features)| features.as_object())
+        .flatten()
+        .filter(|&(_, enabled)| enabled.as_bool() == Some(true))
+        .filter(|&(feature, _)| {
+            CAPABILITY_FEATURES
+                .iter()
+                .any(|&(_, required)| required == feature.as_str())
+        })
+        .map(|(feature, _)| feature.clone())
+        .collect()
+}
+
+/// The Vulkan extensions that the device supports, from the `extensions` of the Vulkan Profiles
+/// format or the `ArrayOfVkExtensionProperties` of the devsim format.
+fn extensions(device: &Map<String, Value>) -> HashSet<String> {
+    let profile_extensions = device
+        .get("extensions")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(Map::keys)
+        .cloned();
+    let devsim_extensions = device
+        .get("ArrayOfVkExtensionProperties")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|properties| properties.get("extensionName")?.as_str())
+        .map(str::to_owned);
+
+    profile_extensions.chain(devsim_extensions).collect()
+}
+
+/// Fail if the SPIR-V module requires capabilities or extensions that the device doesn't support.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn check(
+    spirv_path: &std::path::Path,
+    device_features_path: &std::path::Path,
+) -> anyhow::Result<()> {
+    let device = DeviceFeatures::load(device_features_path)?;
+    let module = inspect::load(spirv_path)?;
+    let mut unsupported = Vec::new();
+
+    for capability in inspect::capabilities(&module) {
+        let name = format!("{capability:?}");
+        let maybe_feature = CAPABILITY_FEATURES
+            .iter()
+            .find(|&&(required_by, _)| required_by == name);
+        if let Some(&(_, feature)) = maybe_feature {
+            if !device.features.contains(feature) {
+                unsupported.push(format!(
+                    "capability `{name}` requires the device feature `{feature}`"
+                ));
+            }
+        }
+    }
+
+    for extension in inspect::extensions(&module) {
+        let vulkan_extension = EXTENSION_ALIASES
+            .iter()
+            .find(|&&(spirv_name, _)| spirv_name == extension)
+            .map_or_else(
+                || extension.replacen("SPV_", "VK_", 1),
+                |&(_, vulkan_name)| vulkan_name.to_owned(),
+            );
+        if !device.extensions.contains(&vulkan_extension) {
+            unsupported.push(format!(
+                "extension `{extension}` requires the device extension `{vulkan_extension}`"
+            ));
+        }
+    }
+
+    if !unsupported.is_empty() {
+        anyhow::bail!(
+            "Shader isn't supported by the device in '{}':\n  {}",
+            device_features_path.display(),
+            unsupported.join("\n  ")
+        );
+    }
+
+    tracing::info!("  Device supports all of the shader's capabilities and extensions");
+
+    Ok(())
+}
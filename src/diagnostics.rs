@@ -0,0 +1,28 @@
+//! Write the diagnostics of the latest compile to a file, so that editor integrations can watch
+//! it rather than parsing the daemon's logs.
+
+use serde_json::json;
+
+use crate::atomic;
+
+/// Overwrite the diagnostics file with the outcome of the latest compile.
+pub fn write(path: &std::path::Path, result: &anyhow::Result<()>) -> anyhow::Result<()> {
+    let diagnostics = match result {
+        Ok(()) => Vec::new(),
+        Err(error) => vec![json!({
+            "level": "error",
+            "message": format!("{error:#}"),
+        })],
+    };
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    let report = json!({
+        "timestamp": timestamp,
+        "success": result.is_ok(),
+        "diagnostics": diagnostics,
+    });
+
+    atomic::write(path, serde_json::to_string_pretty(&report)?.as_bytes())
+}
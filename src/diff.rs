@@ -0,0 +1,120 @@
+//! Compare two SPIR-V binaries and report their semantic differences.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::inspect;
+
+/// The parts of a SPIR-V module that are meaningful to compare between builds. Result IDs aren't
+/// included because they change between otherwise identical builds.
+struct Summary {
+    /// The SPIR-V version from the module header.
+    version: String,
+    /// Declared capabilities.
+    capabilities: BTreeSet<String>,
+    /// Declared extensions.
+    extensions: BTreeSet<String>,
+    /// Entry points, formatted as "[execution model] [name]".
+    entry_points: BTreeSet<String>,
+    /// How many times each instruction is used.
+    opcodes: BTreeMap<&'static str, usize>,
+    /// Size of the binary in bytes.
+    size: usize,
+}
+
+impl Summary {
+    /// Parse and summarise a SPIR-V binary.
+    fn new(path: &std::path::Path) -> anyhow::Result<Self> {
+        let module = inspect::load(path)?;
+
+        let mut opcodes = BTreeMap::new();
+        for instruction in module.all_inst_iter() {
+            *opcodes.entry(instruction.class.opname).or_insert(0) += 1;
+        }
+
+        Ok(Self {
+            version: module.header.as_ref().map_or_else(String::new, |header| {
+                let (major, minor) = header.version();
+                format!("{major}.{minor}")
+            }),
+            capabilities: inspect::capabilities(&module)
+                .iter()
+                .map(|capability| format!("{capability:?}"))
+                .collect(),
+            extensions: inspect::extensions(&module).into_iter().collect(),
+            entry_points: inspect::entry_points(&module)
+                .iter()
+                .map(|(model, name)| format!("{model:?} {name}"))
+                .collect(),
+            opcodes,
+            size: std::fs::metadata(path)?.len() as usize,
+        })
+    }
+}
+
+/// Print the differences between two SPIR-V binaries. Returns whether they're equivalent.
+#[expect(clippy::print_stdout, reason = "The diff is the command's output")]
+pub fn compare(
+    before_path: &std::path::Path,
+    after_path: &std::path::Path,
+) -> anyhow::Result<bool> {
+    let before = Summary::new(before_path)?;
+    let after = Summary::new(after_path)?;
+    let mut is_same = true;
+
+    if before.version != after.version {
+        println!("SPIR-V version: {} -> {}", before.version, after.version);
+        is_same = false;
+    }
+
+    is_same &= !print_set_changes("Capabilities", &before.capabilities, &after.capabilities);
+    is_same &= !print_set_changes("Extensions", &before.extensions, &after.extensions);
+    is_same &= !print_set_changes("Entry points", &before.entry_points, &after.entry_points);
+
+    let opcodes: BTreeSet<&str> = before
+        .opcodes
+        .keys()
+        .chain(after.opcodes.keys())
+        .copied()
+        .collect();
+    let mut opcode_changes = Vec::new();
+    for opcode in opcodes {
+        let before_count = before.opcodes.get(opcode).copied().unwrap_or(0);
+        let after_count = after.opcodes.get(opcode).copied().unwrap_or(0);
+        if before_count != after_count {
+            opcode_changes.push(format!("  Op{opcode}: {before_count} -> {after_count}"));
+        }
+    }
+    if !opcode_changes.is_empty() {
+        println!("Instructions:");
+        println!("{}", opcode_changes.join("\n"));
+        is_same = false;
+    }
+
+    if is_same {
+        println!("No semantic differences");
+    } else {
+        println!("Size: {} -> {} bytes", before.size, after.size);
+    }
+
+    Ok(is_same)
+}
+
+/// Print the items that were removed from and added to a set. Returns whether there were any.
+#[expect(clippy::print_stdout, reason = "The diff is the command's output")]
+fn print_set_changes(title: &str, before: &BTreeSet<String>, after: &BTreeSet<String>) -> bool {
+    let removed: Vec<&String> = before.difference(after).collect();
+    let added: Vec<&String> = after.difference(before).collect();
+    if removed.is_empty() && added.is_empty() {
+        return false;
+    }
+
+    println!("{title}:");
+    for item in removed {
+        println!("  - {item}");
+    }
+    for item in added {
+        println!("  + {item}");
+    }
+
+    true
+}
@@ -0,0 +1,71 @@
+//! Write the human-readable SPIR-V assembly of a module next to it, for debugging miscompiles.
+
+use std::path::{Path, PathBuf};
+
+use rspirv::binary::Disassemble as _;
+use spirv_tools::assembler::Assembler as _;
+use spirv_tools::opt::Optimizer as _;
+
+use crate::atomic;
+use crate::inspect;
+
+/// How the disassembly is written.
+#[derive(Clone, Copy, Debug)]
+pub enum Style {
+    /// The module as it is, with its numeric IDs.
+    Raw,
+    /// For reviewing diffs in version control: IDs are renumbered in order of use and named after
+    /// their debug names where possible, and the header, with its volatile ID bound, is left out.
+    Friendly,
+}
+
+/// Disassemble with stable, named IDs, using `spirv-tools`.
+fn friendly(module_path: &Path) -> anyhow::Result<String> {
+    let words = inspect::words(&std::fs::read(module_path)?)?;
+
+    let mut optimizer = spirv_tools::opt::create(None);
+    optimizer.register_pass(spirv_tools::opt::Passes::CompactIds);
+    let compacted = optimizer
+        .optimize(
+            &words,
+            &mut |message: spirv_tools::error::Message| {
+                tracing::warn!("spirv-opt: {}", message.message);
+            },
+            None,
+        )
+        .map_err(|error| {
+            anyhow::anyhow!(
+                "Couldn't renumber IDs of '{}': {error}",
+                module_path.display()
+            )
+        })?;
+
+    let options = spirv_tools::assembler::DisassembleOptions {
+        use_friendly_names: true,
+        no_header: true,
+        comment: true,
+        ..Default::default()
+    };
+    let assembly = spirv_tools::assembler::create(None)
+        .disassemble(compacted.as_words(), options)
+        .map_err(|error| {
+            anyhow::anyhow!("Couldn't disassemble '{}': {error}", module_path.display())
+        })?;
+
+    Ok(assembly.unwrap_or_default())
+}
+
+/// Write the module's disassembly to a `.spvasm` file with the same path as the module.
+/// Returns the path of the `.spvasm` file.
+pub fn write(module_path: &Path, style: Style) -> anyhow::Result<PathBuf> {
+    let assembly = match style {
+        Style::Raw => inspect::load(module_path)?.disassemble(),
+        Style::Friendly => friendly(module_path)?,
+    };
+    let assembly_path = module_path.with_extension("spvasm");
+    atomic::write(
+        &assembly_path,
+        format!("{}\n", assembly.trim_end()).as_bytes(),
+    )?;
+    Ok(assembly_path)
+}
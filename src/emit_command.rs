@@ -0,0 +1,84 @@
+//! Write a shell script that reproduces the current compile outside of the daemon, for example to
+//! attach to a bug report against `rust-gpu`.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use crate::{atomic, config};
+
+/// Environment variables that affect how `spirv-builder` and cargo compile the shader crate.
+const ENVIRONMENT: &[&str] = &[
+    "RUSTUP_TOOLCHAIN",
+    "RUSTFLAGS",
+    "RUSTGPU_RUSTFLAGS",
+    "RUSTGPU_CODEGEN_ARGS",
+    "CARGO_TERM_COLOR",
+    "RUST_GPU_BUILD_ID",
+    "CARGO_TARGET_DIR",
+    "LD_LIBRARY_PATH",
+    "DYLD_FALLBACK_LIBRARY_PATH",
+    "PATH",
+];
+
+/// Quote a string for POSIX shells.
+fn quote(string: &str) -> String {
+    format!("'{}'", string.replace('\'', r"'\''"))
+}
+
+/// The effective arguments, from both the command line and config files, without the
+/// `--emit-command` that would make the script overwrite itself.
+fn replay_args() -> Vec<OsString> {
+    let mut replay = Vec::new();
+    let mut args = config::effective_args().iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--emit-command" {
+            args.next();
+            continue;
+        }
+        if arg.to_string_lossy().starts_with("--emit-command=") {
+            continue;
+        }
+        replay.push(arg.clone());
+    }
+    replay
+}
+
+/// Write the script. It sets the environment that the compile ran with, then runs this tool with
+/// the same effective arguments, from the same directory.
+pub fn write(path: &Path) -> anyhow::Result<()> {
+    let mut lines = vec![
+        "#!/bin/sh".to_owned(),
+        format!(
+            "# Reproduces a rust-gpu-compiler {} build.",
+            env!("CARGO_PKG_VERSION")
+        ),
+        "set -e".to_owned(),
+        format!("cd {}", quote(&std::env::current_dir()?.to_string_lossy())),
+    ];
+
+    for name in ENVIRONMENT {
+        if let Ok(value) = std::env::var(name) {
+            lines.push(format!("export {name}={}", quote(&value)));
+        }
+    }
+
+    let mut command = vec![quote(&std::env::current_exe()?.to_string_lossy())];
+    command.extend(
+        replay_args()
+            .iter()
+            .map(|arg| quote(&arg.to_string_lossy())),
+    );
+    lines.push(format!("exec {}", command.join(" ")));
+
+    atomic::write(path, format!("{}\n", lines.join("\n")).as_bytes())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    tracing::info!("Wrote compile command to {path:?}");
+
+    Ok(())
+}
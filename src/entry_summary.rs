@@ -0,0 +1,58 @@
+//! Log a one line summary of each of a compiled module's entry points, to confirm at a glance
+//! what the shader exposes.
+
+use rspirv::dr::Operand;
+use rspirv::spirv::{ExecutionMode, ExecutionModel};
+
+use crate::inspect;
+
+/// The local size declared by the entry point's `LocalSize` execution mode, if it has one.
+fn local_size(module: &rspirv::dr::Module, entry_point_id: u32) -> Option<(u32, u32, u32)> {
+    module.execution_modes.iter().find_map(|instruction| {
+        let operands = &instruction.operands;
+        match (operands.first(), operands.get(1)) {
+            (
+                Some(&Operand::IdRef(id)),
+                Some(&Operand::ExecutionMode(ExecutionMode::LocalSize)),
+            ) if id == entry_point_id => {
+                match (operands.get(2), operands.get(3), operands.get(4)) {
+                    (
+                        Some(&Operand::LiteralBit32(x)),
+                        Some(&Operand::LiteralBit32(y)),
+                        Some(&Operand::LiteralBit32(z)),
+                    ) => Some((x, y, z)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Log the name and execution model of each entry point, and the local size of compute ones.
+pub fn log(module_path: &std::path::Path) -> anyhow::Result<()> {
+    let module = inspect::load(module_path)?;
+    for instruction in &module.entry_points {
+        let (
+            Some(&Operand::ExecutionModel(model)),
+            Some(&Operand::IdRef(id)),
+            Some(Operand::LiteralString(name)),
+        ) = (
+            instruction.operands.first(),
+            instruction.operands.get(1),
+            instruction.operands.get(2),
+        )
+        else {
+            continue;
+        };
+
+        match (model, local_size(&module, id)) {
+            (ExecutionModel::GLCompute, Some((x, y, z))) => {
+                tracing::info!("  Entry point `{name}`: {model:?}, local size {x}x{y}x{z}");
+            }
+            _ => tracing::info!("  Entry point `{name}`: {model:?}"),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! Check that entry points declare the execution modes that their stage requires, as a missing
+//! one otherwise causes confusing errors in drivers or later validation.
+
+use rspirv::dr::Operand;
+use rspirv::spirv::{ExecutionMode, ExecutionModel};
+
+use crate::inspect;
+
+/// Execution models and the execution modes that they need one of.
+const REQUIRED_MODES: &[(ExecutionModel, &[ExecutionMode])] = &[
+    (ExecutionModel::Fragment, &[ExecutionMode::OriginUpperLeft]),
+    (
+        ExecutionModel::GLCompute,
+        &[ExecutionMode::LocalSize, ExecutionMode::LocalSizeId],
+    ),
+];
+
+/// Describe each entry point that's missing a required execution mode.
+pub fn missing(module_path: &std::path::Path) -> anyhow::Result<Vec<String>> {
+    let module = inspect::load(module_path)?;
+
+    let modes: Vec<(u32, ExecutionMode)> = module
+        .execution_modes
+        .iter()
+        .filter_map(|instruction| {
+            match (instruction.operands.first(), instruction.operands.get(1)) {
+                (Some(&Operand::IdRef(id)), Some(&Operand::ExecutionMode(mode))) => {
+                    Some((id, mode))
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    let mut missing = Vec::new();
+    for instruction in &module.entry_points {
+        let (
+            Some(&Operand::ExecutionModel(model)),
+            Some(&Operand::IdRef(id)),
+            Some(Operand::LiteralString(name)),
+        ) = (
+            instruction.operands.first(),
+            instruction.operands.get(1),
+            instruction.operands.get(2),
+        )
+        else {
+            continue;
+        };
+        let Some(&(_, required)) = REQUIRED_MODES
+            .iter()
+            .find(|&&(required_by, _)| required_by == model)
+        else {
+            continue;
+        };
+
+        let has_mode = modes
+            .iter()
+            .any(|&(mode_id, mode)| mode_id == id && required.contains(&mode));
+        if !has_mode {
+            let options: Vec<String> = required.iter().map(|mode| format!("{mode:?}")).collect();
+            missing.push(format!(
+                "{model:?} entry point `{name}` has no `{}` execution mode",
+                options.join("` or `")
+            ));
+        }
+    }
+
+    Ok(missing)
+}
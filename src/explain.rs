@@ -0,0 +1,204 @@
+//! Explain what a SPIR-V capability is for and what's needed to use it.
+
+use core::str::FromStr as _;
+
+use crate::device;
+
+/// An explanation of a SPIR-V capability.
+struct Explanation {
+    /// The capability's name, as accepted by `--capability`.
+    capability: &'static str,
+    /// What the capability enables.
+    description: &'static str,
+    /// The Vulkan version or extension that supports it.
+    vulkan: &'static str,
+    /// The `naga` validation capability needed to validate shaders that use it.
+    naga: Option<&'static str>,
+}
+
+/// Explanations of commonly used capabilities. This isn't exhaustive.
+const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        capability: "Shader",
+        description: "Core graphics and compute shader functionality. Always enabled by rust-gpu",
+        vulkan: "Vulkan 1.0",
+        naga: None,
+    },
+    Explanation {
+        capability: "Float64",
+        description: "64-bit floats, ie `f64`",
+        vulkan: "Vulkan 1.0",
+        naga: Some("FLOAT64"),
+    },
+    Explanation {
+        capability: "Int64",
+        description: "64-bit integers, ie `i64` and `u64`",
+        vulkan: "Vulkan 1.0",
+        naga: Some("SHADER_INT64"),
+    },
+    Explanation {
+        capability: "Int16",
+        description: "16-bit integers, ie `i16` and `u16`",
+        vulkan: "Vulkan 1.0",
+        naga: None,
+    },
+    Explanation {
+        capability: "Int8",
+        description: "8-bit integers, ie `i8` and `u8`",
+        vulkan: "Vulkan 1.2, or VK_KHR_shader_float16_int8",
+        naga: None,
+    },
+    Explanation {
+        capability: "Float16",
+        description: "16-bit floats",
+        vulkan: "Vulkan 1.2, or VK_KHR_shader_float16_int8",
+        naga: None,
+    },
+    Explanation {
+        capability: "Int64Atomics",
+        description: "Atomic operations on 64-bit integers",
+        vulkan: "Vulkan 1.2, or VK_KHR_shader_atomic_int64",
+        naga: Some("SHADER_INT64_ATOMIC_ALL_OPS"),
+    },
+    Explanation {
+        capability: "StorageBuffer16BitAccess",
+        description: "16-bit types in storage buffers",
+        vulkan: "Vulkan 1.1, or VK_KHR_16bit_storage",
+        naga: None,
+    },
+    Explanation {
+        capability: "StorageImageExtendedFormats",
+        description: "Additional storage image formats, like `Rg32f` and `R16f`",
+        vulkan: "Vulkan 1.0",
+        naga: None,
+    },
+    Explanation {
+        capability: "StorageImageReadWithoutFormat",
+        description: "Reading from storage images whose format isn't declared in the shader",
+        vulkan: "Vulkan 1.3, or VK_KHR_format_feature_flags2",
+        naga: None,
+    },
+    Explanation {
+        capability: "StorageImageWriteWithoutFormat",
+        description: "Writing to storage images whose format isn't declared in the shader",
+        vulkan: "Vulkan 1.0",
+        naga: None,
+    },
+    Explanation {
+        capability: "SampledCubeArray",
+        description: "Sampling from cube map arrays",
+        vulkan: "Vulkan 1.0",
+        naga: Some("CUBE_ARRAY_TEXTURES"),
+    },
+    Explanation {
+        capability: "ClipDistance",
+        description: "Writing clip distances from vertex shaders",
+        vulkan: "Vulkan 1.0",
+        naga: Some("CLIP_DISTANCE"),
+    },
+    Explanation {
+        capability: "CullDistance",
+        description: "Writing cull distances from vertex shaders",
+        vulkan: "Vulkan 1.0",
+        naga: Some("CULL_DISTANCE"),
+    },
+    Explanation {
+        capability: "SampleRateShading",
+        description: "Per-sample fragment shading, eg reading the sample index",
+        vulkan: "Vulkan 1.0",
+        naga: Some("MULTISAMPLED_SHADING"),
+    },
+    Explanation {
+        capability: "MultiView",
+        description: "Rendering to multiple views, eg for VR, using the view index",
+        vulkan: "Vulkan 1.1, or VK_KHR_multiview",
+        naga: Some("MULTIVIEW"),
+    },
+    Explanation {
+        capability: "DrawParameters",
+        description: "Reading the base vertex, base instance and draw index in vertex shaders",
+        vulkan: "Vulkan 1.1, or VK_KHR_shader_draw_parameters",
+        naga: None,
+    },
+    Explanation {
+        capability: "GroupNonUniform",
+        description: "Basic subgroup operations, like electing a single invocation",
+        vulkan: "Vulkan 1.1",
+        naga: Some("SUBGROUP"),
+    },
+    Explanation {
+        capability: "GroupNonUniformBallot",
+        description: "Subgroup ballot operations",
+        vulkan: "Vulkan 1.1",
+        naga: Some("SUBGROUP"),
+    },
+    Explanation {
+        capability: "RuntimeDescriptorArray",
+        description: "Arrays of descriptors whose size is only known at runtime, ie bindless",
+        vulkan: "Vulkan 1.2, or VK_EXT_descriptor_indexing",
+        naga: None,
+    },
+    Explanation {
+        capability: "ShaderNonUniform",
+        description: "Indexing descriptor arrays with values that differ between invocations",
+        vulkan: "Vulkan 1.2, or VK_EXT_descriptor_indexing",
+        naga: Some("SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING"),
+    },
+    Explanation {
+        capability: "VulkanMemoryModel",
+        description: "The Vulkan memory model, with explicit availability and visibility",
+        vulkan: "Vulkan 1.2, or VK_KHR_vulkan_memory_model",
+        naga: None,
+    },
+    Explanation {
+        capability: "PhysicalStorageBufferAddresses",
+        description: "Pointers to buffer memory, ie buffer device addresses",
+        vulkan: "Vulkan 1.2, or VK_KHR_buffer_device_address",
+        naga: None,
+    },
+    Explanation {
+        capability: "RayQueryKHR",
+        description: "Ray queries from any shader stage",
+        vulkan: "VK_KHR_ray_query",
+        naga: Some("RAY_QUERY"),
+    },
+    Explanation {
+        capability: "RayTracingKHR",
+        description: "Ray tracing pipeline shader stages, eg ray generation and closest hit",
+        vulkan: "VK_KHR_ray_tracing_pipeline",
+        naga: None,
+    },
+];
+
+/// Print an explanation of a SPIR-V capability.
+#[expect(
+    clippy::print_stdout,
+    reason = "The explanation is the command's output"
+)]
+pub fn explain(capability: &str) -> anyhow::Result<()> {
+    let Ok(parsed) = spirv_builder::Capability::from_str(capability) else {
+        anyhow::bail!("Unknown SPIR-V capability `{capability}`");
+    };
+    let name = format!("{parsed:?}");
+
+    println!("{name}");
+    if let Some(explanation) = EXPLANATIONS.iter().find(|item| item.capability == name) {
+        println!("  {}", explanation.description);
+        println!("  Supported by: {}", explanation.vulkan);
+        println!(
+            "  naga validation capability: {}",
+            explanation.naga.unwrap_or("none needed")
+        );
+    } else {
+        println!("  No explanation yet, see https://registry.khronos.org/SPIR-V/");
+    }
+
+    if let Some(&(_, feature)) = device::CAPABILITY_FEATURES
+        .iter()
+        .find(|&&(required_by, _)| required_by == name)
+    {
+        println!("  Vulkan device feature: {feature}");
+    }
+
+    Ok(())
+}
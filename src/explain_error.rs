@@ -0,0 +1,91 @@
+//! Append plain-language explanations to errors that commonly confuse people new to rust-gpu.
+//! rustc's own errors are printed by the cargo that `spirv-builder` runs, so only errors that
+//! reach the daemon, like failed builds and validation failures, can be explained.
+
+/// The rust-gpu book's guide to writing shader crates.
+const SHADER_CRATES_GUIDE: &str =
+    "https://embarkstudios.github.io/rust-gpu/book/writing-shader-crates.html";
+
+/// The rust-gpu book's list of supported platforms and features.
+const PLATFORM_SUPPORT: &str =
+    "https://embarkstudios.github.io/rust-gpu/book/platform-support.html";
+
+/// The Vulkan spec's rules for how buffer contents are laid out.
+const VULKAN_LAYOUT_RULES: &str =
+    "https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#interfaces-resources-layout";
+
+/// A known error and what it usually means.
+struct Gotcha {
+    /// Text that appears in the error.
+    pattern: &'static str,
+    /// What usually causes the error and how to fix it.
+    explanation: &'static str,
+    /// Where to read more.
+    link: &'static str,
+}
+
+/// The most common rust-gpu gotchas.
+const GOTCHAS: &[Gotcha] = &[
+    Gotcha {
+        pattern: "rustc_codegen_spirv",
+        explanation: "The rust-gpu codegen backend couldn't be built or loaded. It only builds \
+                      with the exact nightly toolchain that its version of rust-gpu pins.",
+        link: SHADER_CRATES_GUIDE,
+    },
+    Gotcha {
+        pattern: "BuildFailed",
+        explanation: "rustc rejected the shader crate, its errors are above. Shader crates need \
+                      `#![no_std]`, can't allocate, and can't depend on crates that need `std`.",
+        link: SHADER_CRATES_GUIDE,
+    },
+    Gotcha {
+        pattern: "crate-type",
+        explanation: "Shader crates need `crate-type = [\"dylib\"]` in the `[lib]` section of \
+                      their `Cargo.toml`.",
+        link: SHADER_CRATES_GUIDE,
+    },
+    Gotcha {
+        pattern: "entry points",
+        explanation: "Only functions marked with an attribute like `#[spirv(fragment)]` become \
+                      entry points, and they need to be `pub` in a module the crate root reaches.",
+        link: SHADER_CRATES_GUIDE,
+    },
+    Gotcha {
+        pattern: "layout rules",
+        explanation: "A struct in a buffer isn't laid out how Vulkan expects. Mark it \
+                      `#[repr(C)]` and pad its fields to their alignment, or relax the rules \
+                      with `--scalar-block-layout` or `--relax-block-layout`.",
+        link: VULKAN_LAYOUT_RULES,
+    },
+    Gotcha {
+        pattern: "is required",
+        explanation: "The shader uses a feature that the validation target environment \
+                      doesn't have, such as 64-bit floats or subgroups. Validate for an \
+                      environment that has it with `--validation-target-env`.",
+        link: PLATFORM_SUPPORT,
+    },
+    Gotcha {
+        pattern: "unsupported capability",
+        explanation: "naga, which wgpu uses, can't read modules that declare this capability. \
+                      Avoid the feature that needs it, or validate with `--validate spirv-tools` \
+                      if the module is only for Vulkan.",
+        link: PLATFORM_SUPPORT,
+    },
+    Gotcha {
+        pattern: "unsupported instruction",
+        explanation: "naga doesn't model an instruction in the module yet. These often come \
+                      from pointer casts, `asm!` or newer intrinsics. See \
+                      `--naga-unknown-instructions`, or validate with `--validate spirv-tools`.",
+        link: PLATFORM_SUPPORT,
+    },
+];
+
+/// Explanations of the known gotchas in the error, or `None` if it doesn't match any.
+pub fn explain(error: &str) -> Option<String> {
+    let explanations: Vec<String> = GOTCHAS
+        .iter()
+        .filter(|gotcha| error.contains(gotcha.pattern))
+        .map(|gotcha| format!("💡 {}\n   See {}", gotcha.explanation, gotcha.link))
+        .collect();
+    (!explanations.is_empty()).then(|| explanations.join("\n"))
+}
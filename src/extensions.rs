@@ -0,0 +1,167 @@
+//! A curated list of commonly used SPIR-V extensions, to help find values for `--extension`.
+//! See the SPIR-V Registry for every extension: https://github.com/KhronosGroup/SPIRV-Registry
+
+/// Extensions grouped by purpose, as `(group, [(extension, description), ...])`.
+const EXTENSIONS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Ray tracing",
+        &[
+            (
+                "SPV_KHR_ray_tracing",
+                "Ray tracing pipelines and shader stages",
+            ),
+            ("SPV_KHR_ray_query", "Ray queries from any shader stage"),
+            (
+                "SPV_KHR_ray_cull_mask",
+                "Cull masks for rays traced with ray tracing or ray queries",
+            ),
+        ],
+    ),
+    (
+        "Subgroups",
+        &[
+            (
+                "SPV_KHR_shader_ballot",
+                "Ballot and broadcast operations across a subgroup",
+            ),
+            (
+                "SPV_KHR_subgroup_vote",
+                "Vote operations, like all and any, across a subgroup",
+            ),
+            (
+                "SPV_KHR_subgroup_uniform_control_flow",
+                "Guarantees of uniform control flow within a subgroup",
+            ),
+            (
+                "SPV_KHR_subgroup_rotate",
+                "Rotating values between a subgroup's invocations",
+            ),
+        ],
+    ),
+    (
+        "Memory and storage",
+        &[
+            (
+                "SPV_KHR_physical_storage_buffer",
+                "Pointers to buffers, via buffer device addresses",
+            ),
+            (
+                "SPV_KHR_variable_pointers",
+                "Pointers that can be selected between and stored in variables",
+            ),
+            (
+                "SPV_KHR_16bit_storage",
+                "16-bit types in buffers and interfaces",
+            ),
+            ("SPV_KHR_8bit_storage", "8-bit types in buffers"),
+            (
+                "SPV_KHR_storage_buffer_storage_class",
+                "The `StorageBuffer` storage class",
+            ),
+            ("SPV_KHR_vulkan_memory_model", "The Vulkan memory model"),
+            (
+                "SPV_KHR_workgroup_memory_explicit_layout",
+                "Explicitly laid out workgroup memory",
+            ),
+        ],
+    ),
+    (
+        "Descriptors",
+        &[(
+            "SPV_EXT_descriptor_indexing",
+            "Runtime-sized descriptor arrays and non-uniform indexing",
+        )],
+    ),
+    (
+        "Atomics",
+        &[
+            (
+                "SPV_EXT_shader_atomic_float_add",
+                "Atomic addition of floats",
+            ),
+            (
+                "SPV_EXT_shader_atomic_float_min_max",
+                "Atomic minimum and maximum of floats",
+            ),
+            (
+                "SPV_EXT_shader_image_int64",
+                "64-bit integer atomics on images",
+            ),
+        ],
+    ),
+    (
+        "Shader stages and built-ins",
+        &[
+            (
+                "SPV_KHR_shader_draw_parameters",
+                "The `BaseVertex`, `BaseInstance` and `DrawIndex` built-ins",
+            ),
+            (
+                "SPV_KHR_multiview",
+                "The `ViewIndex` built-in, for multiview rendering",
+            ),
+            ("SPV_EXT_mesh_shader", "Mesh and task shaders"),
+            (
+                "SPV_EXT_fragment_shader_interlock",
+                "Ordered critical sections in fragment shaders",
+            ),
+            (
+                "SPV_EXT_demote_to_helper_invocation",
+                "Demoting fragment invocations to helpers, rather than killing them",
+            ),
+            ("SPV_KHR_fragment_shading_rate", "Variable rate shading"),
+        ],
+    ),
+    (
+        "Floating point and integers",
+        &[
+            (
+                "SPV_KHR_float_controls",
+                "Control of denormals, rounding and signed zeros",
+            ),
+            (
+                "SPV_KHR_integer_dot_product",
+                "Accelerated integer dot products",
+            ),
+        ],
+    ),
+    (
+        "Debugging",
+        &[
+            (
+                "SPV_KHR_non_semantic_info",
+                "Non-semantic instructions, like `debugPrintf`",
+            ),
+            (
+                "SPV_KHR_terminate_invocation",
+                "The `OpTerminateInvocation` instruction",
+            ),
+        ],
+    ),
+];
+
+/// Print the extensions, grouped by purpose.
+#[expect(
+    clippy::print_stdout,
+    reason = "Listing extensions is the flag's output"
+)]
+pub fn list() {
+    let width = EXTENSIONS
+        .iter()
+        .flat_map(|&(_, extensions)| extensions.iter())
+        .map(|&(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+
+    for (index, &(group, extensions)) in EXTENSIONS.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("{group}:");
+        for &(name, description) in extensions {
+            println!("  {name:width$}  {description}");
+        }
+    }
+    println!();
+    println!("See https://github.com/KhronosGroup/SPIRV-Registry for every extension.");
+}
@@ -0,0 +1,79 @@
+//! Decode the generator token in a SPIR-V module's header, which records the tool that produced
+//! the module.
+
+use std::path::Path;
+
+use crate::inspect;
+
+/// The index of the generator word in the SPIR-V header.
+const GENERATOR_WORD: usize = 2;
+
+/// Registered generator tool IDs, from the Khronos SPIR-V registry's `spir-v.xml`.
+const GENERATORS: &[(u16, &str)] = &[
+    (0, "Khronos"),
+    (1, "LunarG"),
+    (2, "Valve"),
+    (3, "Codeplay"),
+    (4, "NVIDIA"),
+    (5, "ARM"),
+    (6, "Khronos LLVM/SPIR-V Translator"),
+    (7, "Khronos SPIR-V Tools Assembler"),
+    (8, "Khronos Glslang Reference Front End"),
+    (9, "Qualcomm"),
+    (10, "AMD"),
+    (11, "Intel"),
+    (12, "Imagination"),
+    (13, "Google Shaderc over Glslang"),
+    (14, "Google spiregg"),
+    (15, "Google rspirv"),
+    (16, "X-LEGEND Mesa-IR/SPIR-V Translator"),
+    (17, "Khronos SPIR-V Tools Linker"),
+    (18, "Wine VKD3D Shader Compiler"),
+    (19, "Tellusim Clay Shader Compiler"),
+    (20, "W3C WebGPU Group WHLSL Shader Translator"),
+    (21, "Google Clspv"),
+    (22, "Google MLIR SPIR-V Serializer"),
+    (23, "Google Tint Compiler"),
+    (24, "Google ANGLE Shader Compiler"),
+    (25, "Netease Games Messiah Shader Compiler"),
+    (26, "Xenia Emulator Microcode Translator"),
+    (27, "Embark Studios Rust GPU Compiler Backend"),
+    (28, "gfx-rs Naga"),
+    (29, "Mikkosoft Productions MSP Shader Compiler"),
+    (30, "SpvGenTwo SPIR-V IR Tools"),
+    (31, "Google Skia SkSL"),
+    (32, "TornadoVM Beehive SPIRV Toolkit"),
+    (33, "DragonJoker ShaderWriter"),
+    (34, "Rayan Hatout SPIRVSmith"),
+    (35, "Saarland University Shady"),
+    (36, "Taichi Graphics Taichi"),
+    (37, "heroseh Hero C Compiler"),
+    (38, "Meta SparkSL"),
+    (39, "SirLynix Nazara ShaderLang Compiler"),
+    (40, "NVIDIA Slang Compiler"),
+    (41, "Zig Software Foundation Zig Compiler"),
+];
+
+/// The name of a registered generator tool.
+fn name(tool: u16) -> Option<&'static str> {
+    GENERATORS
+        .iter()
+        .find(|&&(id, _)| id == tool)
+        .map(|&(_, name)| name)
+}
+
+/// Log the generator tool and its version from the module's header.
+pub fn report(module_path: &Path) -> anyhow::Result<()> {
+    let words = inspect::words(&std::fs::read(module_path)?)?;
+    let Some(&generator) = words.get(GENERATOR_WORD) else {
+        anyhow::bail!("SPIR-V binary is too short to have a header");
+    };
+
+    // The tool ID is in the high 16 bits and the tool's own version number in the low 16 bits.
+    let tool = u16::try_from(generator >> 16)?;
+    let version = u16::try_from(generator & 0xffff)?;
+    let tool_name = name(tool).unwrap_or("unregistered tool");
+    tracing::info!("  Generator: {tool_name} (ID {tool}), version {version} ({generator:#010x})");
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Write GLSL translations of a compiled module, for inspecting and debugging it with desktop GL
+//! tooling. `naga`'s GLSL writer translates one entry point at a time, so each entry point gets
+//! its own file, with the stage taken from the entry point.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::atomic;
+
+/// The conventional file extension for GLSL of the stage.
+const fn extension(stage: naga::ShaderStage) -> &'static str {
+    match stage {
+        naga::ShaderStage::Vertex => "vert",
+        naga::ShaderStage::Fragment => "frag",
+        naga::ShaderStage::Compute => "comp",
+    }
+}
+
+/// Check that `naga`'s GLSL frontend can parse the GLSL back. It only supports some desktop GLSL
+/// versions, and not everything its GLSL writer emits, so failures are only warnings.
+fn check_parses(glsl: &str, stage: naga::ShaderStage, glsl_path: &Path) {
+    let options = naga::front::glsl::Options::from(stage);
+    if let Err(error) = naga::front::glsl::Frontend::default().parse(&options, glsl) {
+        tracing::warn!(
+            "  Generated GLSL '{}' doesn't parse: {}",
+            glsl_path.display(),
+            error.emit_to_string(glsl)
+        );
+    }
+}
+
+/// Write a GLSL file for each of the module's entry points into the directory, named after the
+/// entry point with an extension for its stage, eg `main_fs.frag`. Returns their paths.
+pub fn write(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    directory: &Path,
+    version: u16,
+) -> anyhow::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(directory)?;
+    let options = naga::back::glsl::Options {
+        version: naga::back::glsl::Version::Desktop(version),
+        ..Default::default()
+    };
+
+    let mut paths = Vec::new();
+    for entry_point in &module.entry_points {
+        let pipeline_options = naga::back::glsl::PipelineOptions {
+            shader_stage: entry_point.stage,
+            entry_point: entry_point.name.clone(),
+            multiview: None,
+        };
+        let mut glsl = String::new();
+        naga::back::glsl::Writer::new(
+            &mut glsl,
+            module,
+            info,
+            &options,
+            &pipeline_options,
+            naga::proc::BoundsCheckPolicies::default(),
+        )
+        .and_then(|mut writer| writer.write())
+        .with_context(|| format!("Couldn't translate `{}` to GLSL", entry_point.name))?;
+
+        let filename: String = entry_point
+            .name
+            .chars()
+            .map(|character| {
+                if character.is_ascii_alphanumeric() {
+                    character
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let glsl_path = directory
+            .join(filename)
+            .with_extension(extension(entry_point.stage));
+        check_parses(&glsl, entry_point.stage, &glsl_path);
+        atomic::write(&glsl_path, glsl.as_bytes())?;
+        paths.push(glsl_path);
+    }
+
+    Ok(paths)
+}
@@ -0,0 +1,54 @@
+//! Name output files after a hash of their contents, for cache-busting in web deployments.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::atomic;
+
+/// The manifest, next to the hashed files, that maps logical filenames to hashed filenames.
+const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// A short, stable hash of some bytes. This is 32-bit FNV-1a, which is plenty for cache-busting.
+pub fn content_hash(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Rename the file to include a short hash of its contents, eg `shader.1a2b3c4d.spv`, and record
+/// the new filename in a `manifest.json` next to it. Returns the new path.
+pub fn rename(path: &Path) -> anyhow::Result<PathBuf> {
+    let bytes = std::fs::read(path)?;
+    let logical_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Couldn't get output filename")?;
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .context("Couldn't get output file stem")?;
+
+    let mut hashed_name = format!("{stem}.{:08x}", content_hash(&bytes));
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hashed_name = format!("{hashed_name}.{extension}");
+    }
+    let hashed_path = path.with_file_name(&hashed_name);
+    std::fs::rename(path, &hashed_path)?;
+
+    let manifest_path = path.with_file_name(MANIFEST_FILENAME);
+    let mut manifest = if manifest_path.exists() {
+        let json = std::fs::read_to_string(&manifest_path)?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Couldn't parse '{}'", manifest_path.display()))?
+    } else {
+        serde_json::Map::new()
+    };
+    manifest.insert(logical_name.to_owned(), hashed_name.into());
+    atomic::write(
+        &manifest_path,
+        serde_json::to_string_pretty(&manifest)?.as_bytes(),
+    )?;
+
+    Ok(hashed_path)
+}
@@ -0,0 +1,62 @@
+//! Write an HLSL translation of a compiled module, to reuse shaders in DirectX engines.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use naga::back::hlsl::ShaderModel;
+
+use crate::atomic;
+
+/// Parse an HLSL shader model, eg "6.0".
+pub fn parse_shader_model(shader_model: &str) -> Option<ShaderModel> {
+    match shader_model {
+        "5.0" => Some(ShaderModel::V5_0),
+        "5.1" => Some(ShaderModel::V5_1),
+        "6.0" => Some(ShaderModel::V6_0),
+        "6.1" => Some(ShaderModel::V6_1),
+        "6.2" => Some(ShaderModel::V6_2),
+        "6.3" => Some(ShaderModel::V6_3),
+        "6.4" => Some(ShaderModel::V6_4),
+        "6.5" => Some(ShaderModel::V6_5),
+        "6.6" => Some(ShaderModel::V6_6),
+        "6.7" => Some(ShaderModel::V6_7),
+        _ => None,
+    }
+}
+
+/// Write the module as a `.hlsl` file into the directory, named after the module. Returns its
+/// path.
+pub fn write(
+    module_path: &Path,
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    directory: &Path,
+    shader_model: ShaderModel,
+) -> anyhow::Result<PathBuf> {
+    let options = naga::back::hlsl::Options {
+        shader_model,
+        ..Default::default()
+    };
+    let mut hlsl = String::new();
+    naga::back::hlsl::Writer::new(&mut hlsl, &options)
+        .write(module, info)
+        .map_err(|error| match error {
+            naga::back::hlsl::Error::Unimplemented(feature) => anyhow::anyhow!(
+                "'{}' uses {feature}, which naga can't translate to HLSL yet",
+                module_path.display()
+            ),
+            other => anyhow::Error::new(other).context(format!(
+                "Couldn't translate '{}' to HLSL",
+                module_path.display()
+            )),
+        })?;
+
+    let filename = module_path
+        .file_name()
+        .context("Couldn't get module filename")?;
+    std::fs::create_dir_all(directory)?;
+    let hlsl_path = directory.join(filename).with_extension("hlsl");
+    atomic::write(&hlsl_path, hlsl.as_bytes())?;
+
+    Ok(hlsl_path)
+}
@@ -0,0 +1,41 @@
+//! Run user commands after each compile, eg to regenerate bindings or copy modules into a game's
+//! assets. A failing command is logged, but doesn't stop the daemon.
+
+use std::path::{Path, PathBuf};
+
+/// The environment variable with the paths of the copied modules, separated like `PATH`.
+const OUTPUT_VAR: &str = "RUST_GPU_OUTPUT";
+
+/// The environment variable with the path of the shader crate.
+const CRATE_VAR: &str = "RUST_GPU_CRATE";
+
+/// The command that runs a command line in the platform's shell.
+fn shell(command_line: &str) -> std::process::Command {
+    let mut command = if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(command_line);
+    command
+}
+
+/// Run the command line in the shell, with the paths of the compiled modules and the shader crate
+/// in its environment, and wait for it to finish.
+pub fn run(command_line: &str, outputs: &[PathBuf], crate_path: &Path) {
+    let output_paths = std::env::join_paths(outputs).unwrap_or_default();
+    tracing::info!("Running `{command_line}`");
+    match shell(command_line)
+        .env(OUTPUT_VAR, output_paths)
+        .env(CRATE_VAR, crate_path)
+        .status()
+    {
+        Ok(status) if status.success() => tracing::info!("`{command_line}` succeeded"),
+        Ok(status) => tracing::error!("`{command_line}` failed with {status}"),
+        Err(error) => tracing::error!("Couldn't run `{command_line}`: {error}"),
+    }
+}
@@ -0,0 +1,155 @@
+//! Warn about dependencies of the shader crate that need `std`, and so can't compile for a SPIR-V
+//! target. Without this the failure is a deep and cryptic error from within the dependency. Also
+//! finds the shader crate's local dependencies, for the daemon to watch.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde_json::Value;
+
+/// Commonly depended on crates that need `std`, or an allocator, which shader crates don't have.
+/// Build dependencies run on the host, so they're fine.
+const HOST_ONLY_CRATES: &[(&str, &str)] = &[
+    ("anyhow", "needs an allocator"),
+    ("async-std", "needs threads and an async runtime"),
+    ("chrono", "needs `std` for time"),
+    ("crossbeam", "needs threads"),
+    ("hyper", "needs networking"),
+    ("image", "needs `std` for IO"),
+    ("parking_lot", "needs OS synchronisation primitives"),
+    ("rand", "needs `std` for its default features"),
+    ("rayon", "needs threads"),
+    ("regex", "needs an allocator"),
+    ("reqwest", "needs networking"),
+    ("serde_json", "needs an allocator"),
+    ("tokio", "needs threads and an async runtime"),
+    ("wgpu", "is for the host side of a GPU program"),
+    ("winit", "is for the host side of a GPU program"),
+];
+
+/// Run `cargo metadata` for the shader crate.
+pub fn metadata(crate_path: &Path) -> anyhow::Result<Value> {
+    let output = std::process::Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(crate_path.join("Cargo.toml"))
+        .output()
+        .context("Couldn't run `cargo metadata`")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`cargo metadata` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// The package IDs of the packages that the shader crate depends on, directly or not, excluding
+/// build and dev dependencies.
+fn normal_dependency_ids(metadata: &Value) -> anyhow::Result<HashSet<&str>> {
+    let resolve = metadata
+        .get("resolve")
+        .context("`cargo metadata` has no dependency graph")?;
+    let nodes: HashMap<&str, &Value> = resolve
+        .get("nodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|node| Some((node.get("id")?.as_str()?, node)))
+        .collect();
+    let root = resolve
+        .get("root")
+        .and_then(Value::as_str)
+        .context("`cargo metadata` has no root package")?;
+
+    let mut visited = HashSet::new();
+    let mut pending = vec![root];
+    while let Some(id) = pending.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let dependencies = nodes
+            .get(id)
+            .and_then(|node| node.get("deps"))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten();
+        for dependency in dependencies {
+            let is_normal = dependency
+                .get("dep_kinds")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .any(|kind| kind.get("kind").map_or(true, Value::is_null));
+            if let (true, Some(package)) =
+                (is_normal, dependency.get("pkg").and_then(Value::as_str))
+            {
+                pending.push(package);
+            }
+        }
+    }
+    visited.remove(root);
+
+    Ok(visited)
+}
+
+/// The names of the packages that the shader crate depends on, directly or not, excluding build
+/// and dev dependencies.
+fn normal_dependencies(metadata: &Value) -> anyhow::Result<HashSet<String>> {
+    let names: HashMap<&str, &str> = metadata
+        .get("packages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|package| Some((package.get("id")?.as_str()?, package.get("name")?.as_str()?)))
+        .collect();
+
+    Ok(normal_dependency_ids(metadata)?
+        .into_iter()
+        .filter_map(|id| names.get(id))
+        .map(|&name| name.to_owned())
+        .collect())
+}
+
+/// Warn about each of the shader crate's dependencies that are known to need `std`.
+pub fn check(crate_path: &Path) -> anyhow::Result<()> {
+    let dependencies = normal_dependencies(&metadata(crate_path)?)?;
+    for &(name, reason) in HOST_ONLY_CRATES {
+        if dependencies.contains(name) {
+            tracing::warn!(
+                "Shader crate depends on `{name}`, which {reason} and so probably won't \
+                 compile for the GPU. If it's only used by the host, make it a dev or build \
+                 dependency, or move it to another crate."
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The directories of the shader crate's dependencies that are local, such as path dependencies
+/// and other workspace members, rather than from a registry or git. Excludes build and dev
+/// dependencies.
+pub fn local_dependency_dirs(crate_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let metadata = metadata(crate_path)?;
+    let ids = normal_dependency_ids(&metadata)?;
+
+    Ok(metadata
+        .get("packages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|package| package.get("source").map_or(true, Value::is_null))
+        .filter(|package| {
+            package
+                .get("id")
+                .and_then(Value::as_str)
+                .is_some_and(|id| ids.contains(id))
+        })
+        .filter_map(|package| {
+            let manifest_path = Path::new(package.get("manifest_path")?.as_str()?);
+            Some(manifest_path.parent()?.to_path_buf())
+        })
+        .collect())
+}
@@ -0,0 +1,70 @@
+//! Inspect the contents of a compiled SPIR-V ".spv" binary.
+
+use rspirv::dr::Operand;
+
+/// Parse a SPIR-V binary into `rspirv`'s data representation.
+pub fn load(path: &std::path::Path) -> anyhow::Result<rspirv::dr::Module> {
+    let bytes = std::fs::read(path)?;
+    rspirv::dr::load_bytes(bytes).map_err(|error| {
+        anyhow::anyhow!("Couldn't parse SPIR-V from '{}': {error:?}", path.display())
+    })
+}
+
+/// The capabilities declared by the module's `OpCapability` instructions.
+pub fn capabilities(module: &rspirv::dr::Module) -> Vec<rspirv::spirv::Capability> {
+    module
+        .capabilities
+        .iter()
+        .filter_map(|instruction| match instruction.operands.first() {
+            Some(&Operand::Capability(capability)) => Some(capability),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The extensions declared by the module's `OpExtension` instructions.
+pub fn extensions(module: &rspirv::dr::Module) -> Vec<String> {
+    module
+        .extensions
+        .iter()
+        .filter_map(|instruction| match instruction.operands.first() {
+            Some(Operand::LiteralString(extension)) => Some(extension.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Reinterpret the bytes of a SPIR-V binary as its 32-bit words.
+pub fn words(bytes: &[u8]) -> anyhow::Result<Vec<u32>> {
+    if bytes.len() % 4 != 0 {
+        anyhow::bail!("SPIR-V binary isn't a whole number of 32-bit words");
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|chunk| chunk.try_into().map(u32::from_le_bytes))
+        .collect::<Result<Vec<u32>, _>>()?)
+}
+
+/// The execution model and name of each of the module's entry points.
+pub fn entry_points(module: &rspirv::dr::Module) -> Vec<(rspirv::spirv::ExecutionModel, String)> {
+    module
+        .entry_points
+        .iter()
+        .filter_map(|instruction| {
+            match (instruction.operands.first(), instruction.operands.get(2)) {
+                (Some(&Operand::ExecutionModel(model)), Some(Operand::LiteralString(name))) => {
+                    Some((model, name.clone()))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The addressing model from the module's `OpMemoryModel` instruction.
+pub fn addressing_model(module: &rspirv::dr::Module) -> Option<rspirv::spirv::AddressingModel> {
+    match module.memory_model.as_ref()?.operands.first() {
+        Some(&Operand::AddressingModel(model)) => Some(model),
+        _ => None,
+    }
+}
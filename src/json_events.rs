@@ -0,0 +1,48 @@
+//! Newline-delimited JSON events on stdout for `--format json`, so that editors and other tools
+//! can follow compiles without parsing the logs, which go to stderr instead.
+
+use std::path::PathBuf;
+
+use serde_json::json;
+
+/// How the daemon reports compiles.
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    /// Only human readable logs, on stdout.
+    Text,
+    /// JSON events on stdout, logs on stderr.
+    Json,
+}
+
+/// Print a single event as a line of JSON.
+#[expect(clippy::print_stdout, reason = "JSON events are the flag's output")]
+fn emit(event: &serde_json::Value) {
+    println!("{event}");
+}
+
+/// A compile has started.
+pub fn compile_start() {
+    emit(&json!({ "event": "compile_start" }));
+}
+
+/// A compile succeeded, with an event for each output module.
+pub fn compile_success(outputs: &[PathBuf]) {
+    for output in outputs {
+        let maybe_bytes = std::fs::metadata(output)
+            .map(|metadata| metadata.len())
+            .ok();
+        emit(&json!({
+            "event": "compile_success",
+            "output": output,
+            "bytes": maybe_bytes,
+        }));
+    }
+}
+
+/// A compile, or checking or validating its result, failed, with the error as it's logged.
+pub fn compile_error(message: &str) {
+    emit(&json!({
+        "event": "compile_error",
+        "message": message,
+    }));
+}
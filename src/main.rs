@@ -2,20 +2,174 @@
 
 #![feature(lint_reasons)]
 
+mod allowed_opcodes;
+mod atomic;
+mod build_db;
+mod build_id;
 mod builder;
+mod bundle;
+mod c_header;
+mod cargo_profile;
+mod cargo_target;
+mod codegen_path;
+mod config;
+mod device;
+mod diagnostics;
+mod diff;
+mod disassemble;
+mod emit_command;
+mod entry_summary;
+mod execution_modes;
+mod explain;
+mod explain_error;
+mod extensions;
+mod generator;
+mod glsl;
+mod hashed;
+mod hlsl;
+mod hooks;
+mod host_deps;
+mod inspect;
+mod json_events;
+mod memory;
+mod msl;
+mod naga_compat;
+mod notify_socket;
+mod optimize;
+mod otlp;
+mod parallel;
+mod portable;
+mod process_tree;
+mod push_constants;
+mod reflect;
+mod repl;
+mod repro;
+mod resources;
+mod rust_toolchain;
+mod shutdown;
+mod source_comments;
+mod summary;
+mod summary_json;
+mod tap;
+mod targets;
+mod temp_output;
+mod trace;
 mod validate;
+mod validate_watch;
+mod watch;
+mod watch_events;
+mod wgpu_verify;
 
-use clap::Parser;
+use std::process::ExitCode;
 
-use builder::ShaderCLIArgs;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+use tracing_subscriber::Layer as _;
 
-fn main() {
-    tracing_subscriber::fmt::init();
+fn main() -> ExitCode {
+    let mut args = match config::parse() {
+        Ok(config::Parsed::Args(args)) => *args,
+        Ok(config::Parsed::Done) => return ExitCode::SUCCESS,
+        Err(error) => {
+            #[expect(clippy::print_stderr, reason = "Logging isn't set up yet")]
+            {
+                eprintln!("{error:?}");
+            }
+            return ExitCode::FAILURE;
+        }
+    };
 
-    let args = ShaderCLIArgs::parse();
-    args.start_shader_daemon();
+    let otlp_layer = match args.otlp_endpoint().map(otlp::layer).transpose() {
+        Ok(layer) => layer,
+        Err(error) => {
+            #[expect(clippy::print_stderr, reason = "Logging isn't set up yet")]
+            {
+                eprintln!("Couldn't set up OTLP export: {error:?}");
+            }
+            return ExitCode::FAILURE;
+        }
+    };
 
-    loop {
-        std::thread::park();
+    // With `--format json` stdout is only for the JSON events.
+    let log_writer = if args.is_json_format() {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
+    // Compilation phases are `debug` spans, so that they're only seen by the trace layers.
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(log_writer)
+                .with_filter(LevelFilter::INFO),
+        )
+        .with(
+            args.emit_trace()
+                .map(trace::layer)
+                .with_filter(LevelFilter::DEBUG),
+        )
+        .with(otlp_layer.with_filter(LevelFilter::DEBUG))
+        .with(args.collects_diagnostics().then_some(summary::Layer))
+        .init();
+
+    if let Some(command) = args.subcommand() {
+        return command.run();
+    }
+
+    if args.compiles() {
+        // Installed before any mode runs, so that the REPL and `--no-watch` clean up too.
+        if let Err(error) = shutdown::install() {
+            tracing::warn!("Couldn't install a handler for shutting down cleanly: {error:?}");
+        }
+
+        if let Err(error) = args.prepare() {
+            tracing::error!("{error:?}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Some(path) = args.codegen_backend_path() {
+        if let Err(error) = codegen_path::set_codegen_backend_path(path) {
+            tracing::error!("{error:?}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if args.is_list_extensions() {
+        extensions::list();
+        return ExitCode::SUCCESS;
     }
+
+    if args.is_print_targets() {
+        targets::list();
+        return ExitCode::SUCCESS;
+    }
+
+    if args.is_print_dylib_path() {
+        codegen_path::print_dylib_path();
+        return ExitCode::SUCCESS;
+    }
+
+    if args.is_dry_validate() {
+        return args.dry_validate();
+    }
+
+    if args.is_validate_watch() {
+        return args.validate_watch();
+    }
+
+    let exit_code = if args.is_validate_only() {
+        args.validate_only()
+    } else if args.is_repl() {
+        args.repl()
+    } else if args.is_no_watch() {
+        args.compile_once()
+    } else {
+        args.start_shader_daemon()
+    };
+    temp_output::remove();
+    exit_code
 }
@@ -0,0 +1,60 @@
+//! Measure the peak memory used while compiling. Compilation happens in `cargo` and `rustc`
+//! child processes, so the resident memory of the daemon's whole process tree is periodically
+//! sampled. Only Linux is supported.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::process_tree;
+
+/// How often to sample memory usage.
+const SAMPLE_INTERVAL: core::time::Duration = core::time::Duration::from_millis(100);
+
+/// Tracks the peak memory used by the daemon and its child processes.
+#[derive(Clone)]
+pub struct MemoryProfiler {
+    /// The highest total resident memory, in kB, since the last report.
+    peak_kb: Arc<AtomicU64>,
+}
+
+impl MemoryProfiler {
+    /// Start sampling memory usage in the background.
+    pub fn start() -> Self {
+        let profiler = Self {
+            peak_kb: Arc::new(AtomicU64::new(0)),
+        };
+
+        if cfg!(target_os = "linux") {
+            let peak_kb = Arc::clone(&profiler.peak_kb);
+            std::thread::spawn(move || loop {
+                if let Some(total_kb) = process_tree_rss_kb() {
+                    peak_kb.fetch_max(total_kb, Ordering::Relaxed);
+                }
+                std::thread::sleep(SAMPLE_INTERVAL);
+            });
+        } else {
+            tracing::warn!("Memory profiling is only supported on Linux");
+        }
+
+        profiler
+    }
+
+    /// Log the peak memory used since the last report.
+    pub fn report(&self) {
+        let peak_kb = self.peak_kb.swap(0, Ordering::Relaxed);
+        if peak_kb > 0 {
+            tracing::info!("  Peak memory during compile: {} MiB", peak_kb / 1024);
+        }
+    }
+}
+
+/// The total resident memory, in kB, of this process and all its descendants.
+fn process_tree_rss_kb() -> Option<u64> {
+    let total = process_tree::descendants()?
+        .into_iter()
+        .chain(core::iter::once(std::process::id()))
+        .filter_map(process_tree::rss_kb)
+        .sum();
+
+    Some(total)
+}
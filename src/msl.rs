@@ -0,0 +1,50 @@
+//! Write a Metal Shading Language translation of a compiled module, to reuse shaders on Apple
+//! platforms.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::atomic;
+
+/// Parse a Metal language version, eg "2.1".
+pub fn parse_version(version: &str) -> Option<(u8, u8)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Write the module as a `.metal` file into the directory, named after the module. Returns its
+/// path.
+pub fn write(
+    module_path: &Path,
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    directory: &Path,
+    (major, minor): (u8, u8),
+) -> anyhow::Result<PathBuf> {
+    let options = naga::back::msl::Options {
+        lang_version: (major, minor),
+        ..Default::default()
+    };
+    let (msl, _) = naga::back::msl::write_string(
+        module,
+        info,
+        &options,
+        &naga::back::msl::PipelineOptions::default(),
+    )
+    .with_context(|| {
+        format!(
+            "Couldn't translate '{}' to Metal {major}.{minor}",
+            module_path.display()
+        )
+    })?;
+
+    let filename = module_path
+        .file_name()
+        .context("Couldn't get module filename")?;
+    std::fs::create_dir_all(directory)?;
+    let msl_path = directory.join(filename).with_extension("metal");
+    atomic::write(&msl_path, msl.as_bytes())?;
+
+    Ok(msl_path)
+}
@@ -0,0 +1,108 @@
+//! Check that a shader only needs `naga` features that are available in an older `naga` release.
+//! Useful for engines pinned to an older `wgpu`, whose `naga` can't validate newer features.
+
+use core::str::FromStr;
+
+use crate::validate;
+
+/// A `naga` release. Releases before 22 were numbered `0.[minor]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NagaVersion {
+    /// Major version.
+    major: u32,
+    /// Minor version.
+    minor: u32,
+}
+
+impl NagaVersion {
+    /// Create a version from its major and minor parts.
+    const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl FromStr for NagaVersion {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let mut parts = version.split('.').map(str::parse::<u32>);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => Ok(Self::new(major, minor)),
+            (Some(Ok(major)), None) => Ok(Self::new(major, 0)),
+            _ => Err(format!(
+                "Expected a version like `0.19` or `22.1`, got `{version}`"
+            )),
+        }
+    }
+}
+
+impl core::fmt::Display for NagaVersion {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(formatter, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The first `naga` release whose validator supports each capability. Capabilities that aren't
+/// listed are assumed to be available in all releases.
+const CAPABILITY_VERSIONS: &[(&str, NagaVersion)] = &[
+    ("PRIMITIVE_INDEX", NagaVersion::new(0, 7)),
+    (
+        "SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING",
+        NagaVersion::new(0, 8),
+    ),
+    (
+        "UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING",
+        NagaVersion::new(0, 8),
+    ),
+    ("SAMPLER_NON_UNIFORM_INDEXING", NagaVersion::new(0, 8)),
+    ("CLIP_DISTANCE", NagaVersion::new(0, 9)),
+    ("CULL_DISTANCE", NagaVersion::new(0, 9)),
+    (
+        "STORAGE_TEXTURE_16BIT_NORM_FORMATS",
+        NagaVersion::new(0, 10),
+    ),
+    ("MULTIVIEW", NagaVersion::new(0, 10)),
+    ("EARLY_DEPTH_TEST", NagaVersion::new(0, 11)),
+    ("MULTISAMPLED_SHADING", NagaVersion::new(0, 11)),
+    ("RAY_QUERY", NagaVersion::new(0, 12)),
+    ("DUAL_SOURCE_BLENDING", NagaVersion::new(0, 14)),
+    ("CUBE_ARRAY_TEXTURES", NagaVersion::new(0, 14)),
+    ("SHADER_INT64", NagaVersion::new(0, 20)),
+    ("SUBGROUP", NagaVersion::new(0, 20)),
+    ("SUBGROUP_BARRIER", NagaVersion::new(0, 20)),
+    ("SUBGROUP_VERTEX_STAGE", NagaVersion::new(0, 20)),
+    ("SHADER_INT64_ATOMIC_MIN_MAX", NagaVersion::new(22, 0)),
+    ("SHADER_INT64_ATOMIC_ALL_OPS", NagaVersion::new(22, 0)),
+];
+
+/// Warn about any `naga` capabilities the shader needs that the target `naga` release doesn't
+/// support.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn check(path: &std::path::Path, target: NagaVersion) -> anyhow::Result<()> {
+    let bytes = std::fs::read(path)?;
+    let module = validate::parse_spirv(&bytes)?;
+    let required = validate::required_capabilities(&module)?;
+
+    let mut unsupported = Vec::new();
+    for (name, _) in required.iter_names() {
+        let maybe_version = CAPABILITY_VERSIONS
+            .iter()
+            .find(|&&(capability, _)| capability == name);
+        if let Some(&(_, introduced)) = maybe_version {
+            if introduced > target {
+                unsupported.push(format!("{name} (needs naga {introduced})"));
+            }
+        }
+    }
+
+    if unsupported.is_empty() {
+        tracing::info!("  Shader is compatible with naga {target}");
+    } else {
+        tracing::warn!(
+            "Shader uses features unavailable in naga {target}:\n  {}",
+            unsupported.join("\n  ")
+        );
+    }
+
+    Ok(())
+}
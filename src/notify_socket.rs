@@ -0,0 +1,72 @@
+//! Send a line of JSON for every compile to a Unix socket or named pipe, so that a process on
+//! another machine, for example reading over an SSH-forwarded socket, can react to new modules.
+
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, OnceLock};
+
+use serde_json::json;
+
+use crate::build_id;
+
+/// Queues events for the background thread that writes them, so that a socket or pipe without
+/// a reader doesn't block compiles, and so that events are written in order.
+static EVENTS: OnceLock<mpsc::Sender<(PathBuf, String)>> = OnceLock::new();
+
+/// Notify the socket or pipe of the outcome of a compile.
+pub fn send(path: &Path, modules: &[PathBuf], result: &anyhow::Result<()>) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let event = json!({
+        "event": if result.is_ok() { "compiled" } else { "failed" },
+        "timestamp": timestamp,
+        "build_id": std::env::var(build_id::BUILD_ID_VAR).ok(),
+        "modules": modules,
+        "error": result.as_ref().err().map(|error| format!("{error:#}")),
+    });
+
+    let events = EVENTS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<(PathBuf, String)>();
+        std::thread::spawn(move || {
+            for (event_path, line) in receiver {
+                if let Err(error) = write_line(&event_path, &line) {
+                    tracing::warn!(
+                        "Couldn't notify '{}' of compile: {error:?}",
+                        event_path.display()
+                    );
+                }
+            }
+        });
+        sender
+    });
+    // The receiving thread lives for as long as the process.
+    drop(events.send((path.to_owned(), format!("{event}\n"))));
+}
+
+/// Write a line to a Unix socket, connecting afresh each time, or to a named pipe, which blocks
+/// until there's a reader.
+#[cfg(unix)]
+fn write_line(path: &Path, line: &str) -> anyhow::Result<()> {
+    use std::io::Write as _;
+    use std::os::unix::fs::FileTypeExt as _;
+
+    let file_type = std::fs::metadata(path)?.file_type();
+    if file_type.is_socket() {
+        std::os::unix::net::UnixStream::connect(path)?.write_all(line.as_bytes())?;
+    } else if file_type.is_fifo() {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .write_all(line.as_bytes())?;
+    } else {
+        anyhow::bail!("Not a Unix socket or named pipe");
+    }
+
+    Ok(())
+}
+
+/// Unix sockets and named pipes are only supported on Unix.
+#[cfg(not(unix))]
+fn write_line(_path: &Path, _line: &str) -> anyhow::Result<()> {
+    anyhow::bail!("Notify sockets are only supported on Unix")
+}
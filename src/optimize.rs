@@ -0,0 +1,239 @@
+//! Post-process compiled SPIR-V modules with `spirv-tools`' optimizer.
+
+use spirv_tools::opt::Optimizer as _;
+
+use crate::{atomic, inspect};
+
+/// `spirv-opt` passes that can be run by name with `--opt-pass`, named as `spirv-opt`'s own
+/// command line flags are.
+const NAMED_PASSES: &[(&str, spirv_tools::opt::Passes)] = &[
+    ("ccp", spirv_tools::opt::Passes::CCP),
+    ("code-sink", spirv_tools::opt::Passes::CodeSinking),
+    (
+        "combine-access-chains",
+        spirv_tools::opt::Passes::CombineAccessChains,
+    ),
+    ("compact-ids", spirv_tools::opt::Passes::CompactIds),
+    (
+        "convert-local-access-chains",
+        spirv_tools::opt::Passes::LocalAccessChainConvert,
+    ),
+    (
+        "copy-propagate-arrays",
+        spirv_tools::opt::Passes::CopyPropagateArrays,
+    ),
+    (
+        "eliminate-dead-branches",
+        spirv_tools::opt::Passes::DeadBranchElim,
+    ),
+    (
+        "eliminate-dead-code-aggressive",
+        spirv_tools::opt::Passes::AggressiveDCE,
+    ),
+    (
+        "eliminate-dead-const",
+        spirv_tools::opt::Passes::EliminateDeadConstant,
+    ),
+    (
+        "eliminate-dead-functions",
+        spirv_tools::opt::Passes::EliminateDeadFunctions,
+    ),
+    (
+        "eliminate-dead-inserts",
+        spirv_tools::opt::Passes::DeadInsertElim,
+    ),
+    (
+        "eliminate-dead-members",
+        spirv_tools::opt::Passes::EliminateDeadMembers,
+    ),
+    (
+        "eliminate-insert-extract",
+        spirv_tools::opt::Passes::InsertExtractElim,
+    ),
+    (
+        "eliminate-local-multi-store",
+        spirv_tools::opt::Passes::LocalMultiStoreElim,
+    ),
+    (
+        "eliminate-local-single-block",
+        spirv_tools::opt::Passes::LocalSingleBlockLoadStoreElim,
+    ),
+    (
+        "eliminate-local-single-store",
+        spirv_tools::opt::Passes::LocalSingleStoreElim,
+    ),
+    (
+        "flatten-decorations",
+        spirv_tools::opt::Passes::FlattenDecoration,
+    ),
+    (
+        "fold-spec-const-op-composite",
+        spirv_tools::opt::Passes::FoldSpecConstantOpAndComposite,
+    ),
+    (
+        "freeze-spec-const",
+        spirv_tools::opt::Passes::FreezeSpecConstantValue,
+    ),
+    (
+        "inline-entry-points-exhaustive",
+        spirv_tools::opt::Passes::InlineExhaustive,
+    ),
+    (
+        "inline-entry-points-opaque",
+        spirv_tools::opt::Passes::InlineOpaque,
+    ),
+    (
+        "local-redundancy-elimination",
+        spirv_tools::opt::Passes::LocalRedundancyElimination,
+    ),
+    (
+        "loop-invariant-code-motion",
+        spirv_tools::opt::Passes::LoopInvariantCodeMotion,
+    ),
+    ("merge-blocks", spirv_tools::opt::Passes::BlockMerge),
+    ("merge-return", spirv_tools::opt::Passes::MergeReturn),
+    ("private-to-local", spirv_tools::opt::Passes::PrivateToLocal),
+    (
+        "redundancy-elimination",
+        spirv_tools::opt::Passes::RedundancyElimination,
+    ),
+    ("reduce-load-size", spirv_tools::opt::Passes::ReduceLoadSize),
+    (
+        "remove-duplicates",
+        spirv_tools::opt::Passes::RemoveDuplicates,
+    ),
+    (
+        "strength-reduction",
+        spirv_tools::opt::Passes::StrengthReduction,
+    ),
+    ("strip-debug", spirv_tools::opt::Passes::StripDebugInfo),
+    ("strip-reflect", spirv_tools::opt::Passes::StripReflectInfo),
+    ("unify-const", spirv_tools::opt::Passes::UnifyConstant),
+    ("vector-dce", spirv_tools::opt::Passes::VectorDCE),
+];
+
+/// Look up a `spirv-opt` pass by its command line name, eg "merge-blocks".
+pub fn pass(name: &str) -> Option<spirv_tools::opt::Passes> {
+    NAMED_PASSES
+        .iter()
+        .find(|&&(pass_name, _)| pass_name == name)
+        .map(|&(_, pass)| pass)
+}
+
+/// The names of all the passes that can be run with `--opt-pass`.
+pub fn pass_names() -> Vec<&'static str> {
+    NAMED_PASSES.iter().map(|&(name, _)| name).collect()
+}
+
+/// Run the given `spirv-opt` passes over a SPIR-V binary, rewriting it in place.
+/// Returns the size in bytes of the binary before and after.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn run_passes(
+    path: &std::path::Path,
+    passes: &[spirv_tools::opt::Passes],
+) -> anyhow::Result<(usize, usize)> {
+    let mut optimizer = spirv_tools::opt::create(None);
+    for pass in passes {
+        optimizer.register_pass(*pass);
+    }
+    run_optimizer(path, &optimizer)
+}
+
+/// Run an optimizer, with its passes already registered, over a SPIR-V binary, rewriting it in
+/// place. Returns the size in bytes of the binary before and after.
+fn run_optimizer<O: spirv_tools::opt::Optimizer>(
+    path: &std::path::Path,
+    optimizer: &O,
+) -> anyhow::Result<(usize, usize)> {
+    let bytes = std::fs::read(path)?;
+    let words = inspect::words(&bytes)?;
+
+    let optimized = optimizer
+        .optimize(
+            &words,
+            &mut |message: spirv_tools::error::Message| {
+                tracing::warn!("spirv-opt: {}", message.message);
+            },
+            None,
+        )
+        .map_err(|error| anyhow::anyhow!("spirv-opt failed on '{}': {error}", path.display()))?;
+
+    atomic::write(path, optimized.as_bytes())?;
+
+    Ok((bytes.len(), optimized.as_bytes().len()))
+}
+
+/// Which of `spirv-opt`'s recommended sets of passes to optimize with.
+#[derive(Clone, Copy, Debug)]
+pub enum OptimizeLevel {
+    /// Don't optimize.
+    None,
+    /// Make the module smaller.
+    Size,
+    /// Make the module faster.
+    Performance,
+}
+
+/// Optimize a SPIR-V binary in place with the passes for the level, logging how much smaller it
+/// got.
+pub fn optimize(path: &std::path::Path, level: OptimizeLevel) -> anyhow::Result<()> {
+    let mut optimizer = spirv_tools::opt::create(None);
+    match level {
+        OptimizeLevel::None => return Ok(()),
+        OptimizeLevel::Size => optimizer.register_size_passes(),
+        OptimizeLevel::Performance => optimizer.register_performance_passes(),
+    };
+
+    let (before, after) = run_optimizer(path, &optimizer)?;
+    tracing::info!(
+        "  Optimized for {level:?}: {before} -> {after} bytes ({:.1}% smaller)",
+        100.0 - (after as f32 / before as f32 * 100.0)
+    );
+    Ok(())
+}
+
+/// Run the named `spirv-opt` passes, in order, over a SPIR-V binary, rewriting it in place.
+pub fn run_named_passes(path: &std::path::Path, names: &[String]) -> anyhow::Result<()> {
+    let passes = names
+        .iter()
+        .map(|name| pass(name).ok_or_else(|| anyhow::anyhow!("Unknown spirv-opt pass `{name}`")))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let (before, after) = run_passes(path, &passes)?;
+    tracing::info!(
+        "  Ran spirv-opt passes {}: {before} -> {after} bytes",
+        names.join(", ")
+    );
+    Ok(())
+}
+
+/// Strip all debug and reflection information from a SPIR-V binary.
+pub fn strip(path: &std::path::Path) -> anyhow::Result<()> {
+    let (before, after) = run_passes(
+        path,
+        &[
+            spirv_tools::opt::Passes::StripDebugInfo,
+            spirv_tools::opt::Passes::StripReflectInfo,
+        ],
+    )?;
+    tracing::info!(
+        "  Stripped debug info: {before} -> {after} bytes ({:.1}% smaller)",
+        100.0 - (after as f32 / before as f32 * 100.0)
+    );
+    Ok(())
+}
+
+/// Normalise a SPIR-V binary so that semantically identical modules are byte-identical. This
+/// strips non-semantic information, removes duplicate declarations and renumbers result IDs.
+pub fn normalize(path: &std::path::Path) -> anyhow::Result<()> {
+    let (before, after) = run_passes(
+        path,
+        &[
+            spirv_tools::opt::Passes::StripDebugInfo,
+            spirv_tools::opt::Passes::StripReflectInfo,
+            spirv_tools::opt::Passes::RemoveDuplicates,
+            spirv_tools::opt::Passes::CompactIds,
+        ],
+    )?;
+    tracing::info!("  Normalized: {before} -> {after} bytes");
+    Ok(())
+}
@@ -0,0 +1,34 @@
+//! Export the compilation phases as OpenTelemetry spans to an OTLP endpoint, so that shader
+//! build performance can be seen alongside other services.
+
+use opentelemetry::trace::TracerProvider as _;
+use tracing_subscriber::registry::LookupSpan;
+
+/// The service name that spans are exported under.
+const SERVICE_NAME: &str = "rust-gpu-compiler";
+
+/// A `tracing` layer that exports spans to the OTLP endpoint over HTTP. Spans are exported as
+/// soon as they end, as the daemon never exits cleanly.
+pub fn layer<S>(
+    endpoint: &str,
+) -> anyhow::Result<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(opentelemetry_sdk::trace::Config::default().with_resource(
+            opentelemetry_sdk::Resource::new(vec![opentelemetry::KeyValue::new(
+                "service.name",
+                SERVICE_NAME,
+            )]),
+        ))
+        .install_simple()?;
+
+    Ok(tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)))
+}
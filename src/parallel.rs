@@ -0,0 +1,47 @@
+//! Run jobs on a bounded number of threads.
+
+use core::num::NonZeroUsize;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The default number of threads: one per CPU.
+pub fn default_jobs() -> NonZeroUsize {
+    std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Run a fallible job for each item on up to `jobs` threads. Returns each item's result, in the
+/// same order as the items.
+pub fn run<T, F>(items: &[T], jobs: NonZeroUsize, job: F) -> Vec<anyhow::Result<()>>
+where
+    T: Sync,
+    F: Fn(&T) -> anyhow::Result<()> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<anyhow::Result<()>>>> =
+        items.iter().map(|_| Mutex::new(None)).collect();
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.get().min(items.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = job(item);
+                if let Some(Ok(mut slot)) = results.get(index).map(Mutex::lock) {
+                    *slot = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| Err(anyhow::anyhow!("Job didn't complete")))
+        })
+        .collect()
+}
@@ -0,0 +1,54 @@
+//! A "portable" profile for shaders that should run on as many devices as possible. The baseline
+//! is what every Vulkan 1.1 device supports without enabling any optional features or extensions.
+
+use crate::inspect;
+
+/// Capabilities that every Vulkan 1.1 device supports. See the "SPIR-V Environment" appendix of
+/// the Vulkan spec.
+const PORTABLE_CAPABILITIES: &[&str] = &[
+    "Matrix",
+    "Shader",
+    "InputAttachment",
+    "Sampled1D",
+    "Image1D",
+    "SampledBuffer",
+    "ImageBuffer",
+    "ImageQuery",
+    "DerivativeControl",
+    "GroupNonUniform",
+];
+
+/// Whether every Vulkan 1.1 device supports the capability, named as in `--capability`.
+pub fn is_portable_capability(capability: &str) -> bool {
+    PORTABLE_CAPABILITIES.contains(&capability)
+}
+
+/// Warn about everything in the module that's outside the portable baseline. Every extension is,
+/// as they all need enabling on the device.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn check(module_path: &std::path::Path) -> anyhow::Result<()> {
+    let module = inspect::load(module_path)?;
+
+    let mut non_portable: Vec<String> = inspect::capabilities(&module)
+        .iter()
+        .map(|capability| format!("{capability:?}"))
+        .filter(|capability| !is_portable_capability(capability))
+        .map(|capability| format!("capability `{capability}`"))
+        .collect();
+    non_portable.extend(
+        inspect::extensions(&module)
+            .iter()
+            .map(|extension| format!("extension `{extension}`")),
+    );
+
+    if non_portable.is_empty() {
+        tracing::info!("  Shader is portable to every Vulkan 1.1 device");
+    } else {
+        tracing::warn!(
+            "Shader isn't portable, it needs:\n  {}",
+            non_portable.join("\n  ")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Inspect the daemon's child processes. Compilation happens in `cargo` and `rustc` processes
+//! spawned by `spirv-builder`, so this is the only way to observe them. Only Linux is supported,
+//! via `/proc`.
+
+use std::collections::HashMap;
+
+/// Every process's parent PID, by PID, or `None` if they can't be found.
+fn parents() -> Option<HashMap<u32, u32>> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+
+    let mut parents = HashMap::new();
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if let Some(parent) = parent_pid(pid) {
+            parents.insert(pid, parent);
+        }
+    }
+
+    Some(parents)
+}
+
+/// The PIDs of all of `root`'s descendants.
+fn descendants_of(root: u32, parents: &HashMap<u32, u32>) -> Vec<u32> {
+    parents
+        .keys()
+        .copied()
+        .filter(|&pid| is_descendant(pid, root, parents))
+        .collect()
+}
+
+/// The PIDs of all of this process's descendants, or `None` if they can't be found.
+pub fn descendants() -> Option<Vec<u32>> {
+    Some(descendants_of(std::process::id(), &parents()?))
+}
+
+/// Whether `pid` is a descendant of `ancestor`.
+fn is_descendant(pid: u32, ancestor: u32, parents: &HashMap<u32, u32>) -> bool {
+    let mut current = pid;
+    while let Some(&parent) = parents.get(&current) {
+        if parent == ancestor {
+            return true;
+        }
+        current = parent;
+    }
+    false
+}
+
+/// Read a process's parent PID from `/proc/[pid]/stat`.
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The process name is in parentheses and may contain spaces, so skip past it.
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Read a process's resident memory, in kB, from `/proc/[pid]/status`.
+pub fn rss_kb(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Whether the process is `cargo`, going by `/proc/[pid]/comm`.
+fn is_cargo(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .is_ok_and(|name| name.trim_end() == "cargo")
+}
+
+/// Terminate the `cargo` processes that `spirv-builder` started to compile, and everything they
+/// started, such as `rustc`. Other children of this process, like `--on-compile` hooks, are left
+/// alone.
+pub fn kill_compiles() {
+    let Some(parents) = parents() else {
+        tracing::error!("Couldn't find the compile's processes to kill, only Linux is supported");
+        return;
+    };
+
+    let root = std::process::id();
+    let pids: Vec<u32> = parents
+        .iter()
+        .filter(|&(&pid, &parent)| parent == root && is_cargo(pid))
+        .flat_map(|(&cargo, _)| core::iter::once(cargo).chain(descendants_of(cargo, &parents)))
+        .collect();
+    if pids.is_empty() {
+        tracing::warn!("Couldn't find the compile's `cargo` process to kill");
+        return;
+    }
+
+    let result = std::process::Command::new("kill")
+        .args(pids.iter().map(u32::to_string))
+        .status();
+    if let Err(error) = result {
+        tracing::error!("Couldn't kill the compile's processes {pids:?}: {error}");
+    }
+}
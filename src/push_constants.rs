@@ -0,0 +1,120 @@
+//! Report the layout of a module's push-constant blocks, so that it can be checked against what
+//! the host pushes. Misaligned or oversized blocks otherwise only fail at runtime.
+
+/// How strictly to check push-constant layouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutCheck {
+    /// Log each block's size and member offsets.
+    Report,
+    /// Also fail if there's padding between any of a block's members.
+    Strict,
+}
+
+/// A member of a push-constant block.
+struct Member {
+    /// The member's name.
+    name: String,
+    /// Where the member starts, in bytes from the start of the block.
+    offset: u32,
+    /// The member's size in bytes.
+    size: u32,
+}
+
+/// The members of a push-constant variable, and the block's total size. A variable that isn't a
+/// struct is a block with a single member.
+fn members(
+    module: &naga::Module,
+    variable: &naga::GlobalVariable,
+) -> anyhow::Result<(Vec<Member>, u32)> {
+    let context = module.to_ctx();
+    let ty = module.types.get_handle(variable.ty)?;
+    let naga::TypeInner::Struct {
+        members: ref struct_members,
+        span,
+    } = ty.inner
+    else {
+        let size = ty.inner.size(context);
+        let member = Member {
+            name: variable.name.clone().unwrap_or_default(),
+            offset: 0,
+            size,
+        };
+        return Ok((vec![member], size));
+    };
+
+    let members = struct_members
+        .iter()
+        .enumerate()
+        .map(|(index, member)| {
+            Ok(Member {
+                name: member
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("member {index}")),
+                offset: member.offset,
+                size: module.types.get_handle(member.ty)?.inner.size(context),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok((members, span))
+}
+
+/// Log the layout of each push-constant block, failing if a block has gaps between its members
+/// and the check is strict, or if it's bigger than `max_bytes`.
+pub fn check(
+    module: &naga::Module,
+    maybe_layout_check: Option<LayoutCheck>,
+    maybe_max_bytes: Option<u32>,
+) -> anyhow::Result<()> {
+    let mut failures = Vec::new();
+    for (_, variable) in module.global_variables.iter() {
+        if variable.space != naga::AddressSpace::PushConstant {
+            continue;
+        }
+        let name = variable.name.as_deref().unwrap_or("unnamed");
+        let (members, size) = members(module, variable)?;
+
+        let mut layout = vec![format!("  push constants `{name}`: {size} bytes")];
+        let mut end = 0_u32;
+        for member in &members {
+            if member.offset > end {
+                let gap = member.offset.saturating_sub(end);
+                layout.push(format!("    {gap} byte gap"));
+                if matches!(maybe_layout_check, Some(LayoutCheck::Strict)) {
+                    failures.push(format!(
+                        "`{name}` has a {gap} byte gap before `{}`",
+                        member.name
+                    ));
+                }
+            }
+            layout.push(format!(
+                "    {:>4}: `{}`, {} bytes",
+                member.offset, member.name, member.size
+            ));
+            end = member.offset.saturating_add(member.size);
+        }
+        if size > end {
+            layout.push(format!(
+                "    {} bytes of trailing padding",
+                size.saturating_sub(end)
+            ));
+        }
+        tracing::info!("{}", layout.join("\n"));
+
+        if let Some(max_bytes) = maybe_max_bytes.filter(|max_bytes| size > *max_bytes) {
+            failures.push(format!(
+                "`{name}` is {size} bytes, more than the {max_bytes} byte limit"
+            ));
+        }
+    }
+
+    if !failures.is_empty() {
+        anyhow::bail!(
+            "Push-constant layout check failed:\n  {}",
+            failures.join("\n  ")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! Reflect on compiled modules' resource bindings and interfaces, so that an engine can build
+//! pipeline layouts without parsing SPIR-V itself.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde_json::json;
+
+use crate::{atomic, validate};
+
+/// A short name for the kind of a type, eg "struct" or "image".
+fn type_kind(module: &naga::Module, ty: naga::Handle<naga::Type>) -> &'static str {
+    let Ok(ty) = module.types.get_handle(ty) else {
+        return "unknown";
+    };
+    match ty.inner {
+        naga::TypeInner::Scalar(_) => "scalar",
+        naga::TypeInner::Vector { .. } => "vector",
+        naga::TypeInner::Matrix { .. } => "matrix",
+        naga::TypeInner::Atomic(_) => "atomic",
+        naga::TypeInner::Pointer { .. } | naga::TypeInner::ValuePointer { .. } => "pointer",
+        naga::TypeInner::Array { .. } => "array",
+        naga::TypeInner::Struct { .. } => "struct",
+        naga::TypeInner::Image { .. } => "image",
+        naga::TypeInner::Sampler { .. } => "sampler",
+        naga::TypeInner::AccelerationStructure => "acceleration_structure",
+        naga::TypeInner::RayQuery => "ray_query",
+        naga::TypeInner::BindingArray { .. } => "binding_array",
+    }
+}
+
+/// The name of an address space, as WGSL writes it.
+const fn address_space(space: naga::AddressSpace) -> &'static str {
+    match space {
+        naga::AddressSpace::Function => "function",
+        naga::AddressSpace::Private => "private",
+        naga::AddressSpace::WorkGroup => "workgroup",
+        naga::AddressSpace::Uniform => "uniform",
+        naga::AddressSpace::Storage { .. } => "storage",
+        naga::AddressSpace::Handle => "handle",
+        naga::AddressSpace::PushConstant => "push_constant",
+    }
+}
+
+/// The module's global variables, with their bindings.
+fn globals(module: &naga::Module) -> Vec<serde_json::Value> {
+    module
+        .global_variables
+        .iter()
+        .map(|(_, global)| {
+            json!({
+                "name": global.name,
+                "address_space": address_space(global.space),
+                "group": global.binding.as_ref().map(|binding| binding.group),
+                "binding": global.binding.as_ref().map(|binding| binding.binding),
+                "type": type_kind(module, global.ty),
+            })
+        })
+        .collect()
+}
+
+/// Add the interface variables of a value with this type and binding, looking inside structs,
+/// whose members have their own bindings.
+fn add_interface(
+    module: &naga::Module,
+    name: Option<&str>,
+    ty: naga::Handle<naga::Type>,
+    maybe_binding: Option<&naga::Binding>,
+    interface: &mut Vec<serde_json::Value>,
+) {
+    match maybe_binding {
+        Some(&naga::Binding::Location { location, .. }) => {
+            interface.push(json!({ "name": name, "location": location }));
+        }
+        Some(&naga::Binding::BuiltIn(built_in)) => {
+            interface.push(json!({ "name": name, "built_in": format!("{built_in:?}") }));
+        }
+        None => {
+            let Ok(ty) = module.types.get_handle(ty) else {
+                return;
+            };
+            if let naga::TypeInner::Struct { ref members, .. } = ty.inner {
+                for member in members {
+                    add_interface(
+                        module,
+                        member.name.as_deref(),
+                        member.ty,
+                        member.binding.as_ref(),
+                        interface,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The module's entry points, with their workgroup sizes and interfaces.
+fn entry_points(module: &naga::Module) -> Vec<serde_json::Value> {
+    module
+        .entry_points
+        .iter()
+        .map(|entry_point| {
+            let mut inputs = Vec::new();
+            for argument in &entry_point.function.arguments {
+                add_interface(
+                    module,
+                    argument.name.as_deref(),
+                    argument.ty,
+                    argument.binding.as_ref(),
+                    &mut inputs,
+                );
+            }
+            let mut outputs = Vec::new();
+            if let Some(result) = entry_point.function.result.as_ref() {
+                add_interface(
+                    module,
+                    None,
+                    result.ty,
+                    result.binding.as_ref(),
+                    &mut outputs,
+                );
+            }
+
+            let workgroup_size = (entry_point.stage == naga::ShaderStage::Compute)
+                .then_some(entry_point.workgroup_size);
+            json!({
+                "name": entry_point.name,
+                "stage": format!("{:?}", entry_point.stage),
+                "workgroup_size": workgroup_size,
+                "inputs": inputs,
+                "outputs": outputs,
+            })
+        })
+        .collect()
+}
+
+/// Write a JSON reflection of the modules to `reflect_path`, keyed by their filenames.
+pub fn write(reflect_path: &Path, module_paths: &[PathBuf]) -> anyhow::Result<()> {
+    let mut modules = serde_json::Map::new();
+    for module_path in module_paths {
+        let filename = module_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("Couldn't get module filename")?;
+        let module = validate::parse_spirv(&std::fs::read(module_path)?)?;
+        modules.insert(
+            filename.to_owned(),
+            json!({
+                "globals": globals(&module),
+                "entry_points": entry_points(&module),
+            }),
+        );
+    }
+
+    atomic::write(
+        reflect_path,
+        serde_json::to_string_pretty(&serde_json::Value::Object(modules))?.as_bytes(),
+    )
+}
@@ -0,0 +1,83 @@
+//! Commands for the interactive `--repl` mode, which changes compile settings and recompiles
+//! without restarting. Changing only validation settings re-validates the last compile's modules
+//! instead of recompiling.
+
+use core::str::FromStr as _;
+
+/// The commands the REPL understands.
+pub const HELP: &str = "Commands that recompile:
+  capability <NAME>      Enable the SPIR-V capability, or disable it if it's already enabled
+  extension <NAME>       Enable the SPIR-V extension, or disable it if it's already enabled
+  target <TARGET>        Change the rust-gpu compile target
+  debug                  Compile shaders in debug mode
+  release                Compile shaders in release mode
+  compile                Recompile without changing any settings
+Commands that only re-validate the last compile:
+  validate <OPTION|off>  Change how modules are validated, like `--validate`
+  target-env <ENVS>      Change the comma separated validation target environments, like
+                         `--validation-target-env`
+Other commands:
+  settings               Print the current settings
+  help                   Print this help
+  quit                   Stop the REPL";
+
+/// A command read from stdin.
+pub enum Command {
+    /// Toggle a SPIR-V capability.
+    Capability(spirv_builder::Capability),
+    /// Toggle a SPIR-V extension.
+    Extension(String),
+    /// Change the compile target.
+    Target(String),
+    /// Switch between debug, `true`, and release, `false`, mode.
+    Debug(bool),
+    /// Change the validation option, or turn validation off.
+    Validate(String),
+    /// Change the validation target environments.
+    TargetEnv(String),
+    /// Recompile with the current settings.
+    Compile,
+    /// Print the current settings.
+    Settings,
+    /// Print the commands.
+    Help,
+    /// Stop the REPL.
+    Quit,
+}
+
+impl Command {
+    /// Whether the command only changes validation settings, so the last compile's modules can be
+    /// re-validated without recompiling.
+    pub const fn is_validation_only(&self) -> bool {
+        matches!(*self, Self::Validate(_) | Self::TargetEnv(_))
+    }
+
+    /// Parse a line of input. Blank lines are `None`.
+    pub fn parse(line: &str) -> Option<anyhow::Result<Self>> {
+        let mut words = line.split_whitespace();
+        let name = words.next()?;
+        let argument = words.next();
+
+        let command = match (name, argument) {
+            ("capability", Some(capability)) => spirv_builder::Capability::from_str(capability)
+                .map(Self::Capability)
+                .map_err(|()| anyhow::anyhow!("Unknown SPIR-V capability '{capability}'")),
+            ("extension", Some(extension)) => Ok(Self::Extension(extension.to_owned())),
+            ("target", Some(target)) => Ok(Self::Target(target.to_owned())),
+            ("debug", None) => Ok(Self::Debug(true)),
+            ("release", None) => Ok(Self::Debug(false)),
+            ("validate", Some(option)) => Ok(Self::Validate(option.to_owned())),
+            ("target-env", Some(envs)) => Ok(Self::TargetEnv(envs.to_owned())),
+            ("compile", None) => Ok(Self::Compile),
+            ("settings", None) => Ok(Self::Settings),
+            ("help", None) => Ok(Self::Help),
+            ("quit" | "exit", None) => Ok(Self::Quit),
+            _ => Err(anyhow::anyhow!(
+                "Couldn't understand '{}', type `help` for the commands",
+                line.trim()
+            )),
+        };
+
+        Some(command)
+    }
+}
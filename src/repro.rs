@@ -0,0 +1,101 @@
+//! Write a reproducibility manifest next to a compiled module, recording the inputs it was built
+//! from, so that a given `.spv` can be traced back to specific sources and tools.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use sha2::Digest as _;
+
+use crate::atomic;
+
+/// Hex-encoded SHA-256 of a file's contents.
+fn sha256(path: &Path) -> anyhow::Result<String> {
+    let bytes = std::fs::read(path)?;
+    Ok(sha2::Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Recursively collect every file in a directory.
+fn files_in(directory: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files_in(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The `Cargo.lock` that applies to the crate: its own or its workspace's.
+fn find_cargo_lock(crate_path: &Path) -> Option<PathBuf> {
+    crate_path
+        .canonicalize()
+        .ok()?
+        .ancestors()
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|lockfile| lockfile.exists())
+}
+
+/// The Rust toolchain that the shader crate is compiled with.
+fn toolchain(crate_path: &Path) -> String {
+    for filename in ["rust-toolchain.toml", "rust-toolchain"] {
+        let Ok(contents) = std::fs::read_to_string(crate_path.join(filename)) else {
+            continue;
+        };
+        let maybe_channel = contents.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "channel").then(|| value.trim().trim_matches('"').to_owned())
+        });
+        // The legacy `rust-toolchain` file can also just contain the channel name.
+        return maybe_channel.unwrap_or_else(|| contents.trim().to_owned());
+    }
+
+    std::env::var("RUSTUP_TOOLCHAIN").unwrap_or_else(|_| "unknown".to_owned())
+}
+
+/// Write `[module].repro.json` next to the module.
+pub fn write_manifest(module_path: &Path, crate_path: &Path) -> anyhow::Result<()> {
+    let mut source_files = vec![crate_path.join("Cargo.toml")];
+    files_in(&crate_path.join("src"), &mut source_files)?;
+
+    let mut sources = BTreeMap::new();
+    for file in source_files {
+        let relative = file.strip_prefix(crate_path).unwrap_or(&file);
+        sources.insert(relative.display().to_string(), sha256(&file)?);
+    }
+
+    let cargo_lock = match find_cargo_lock(crate_path) {
+        Some(lockfile) => json!({
+            "path": lockfile.display().to_string(),
+            "sha256": sha256(&lockfile)?,
+        }),
+        None => serde_json::Value::Null,
+    };
+
+    let manifest = json!({
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "toolchain": toolchain(crate_path),
+        "module": {
+            "path": module_path.display().to_string(),
+            "sha256": sha256(module_path)?,
+        },
+        "cargo_lock": cargo_lock,
+        "sources": sources,
+    });
+
+    let mut manifest_path = module_path.as_os_str().to_owned();
+    manifest_path.push(".repro.json");
+    let manifest_path = PathBuf::from(manifest_path);
+    atomic::write(
+        &manifest_path,
+        serde_json::to_string_pretty(&manifest)?.as_bytes(),
+    )?;
+    tracing::info!("  Wrote reproducibility manifest to {manifest_path:?}");
+
+    Ok(())
+}
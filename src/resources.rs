@@ -0,0 +1,197 @@
+//! Check that the module declares the capabilities its image and sampler resources need.
+//! `spirv-val` checks the instructions that use images, but a module can declare an image type
+//! that needs a capability without ever tripping that check, only to be rejected by drivers.
+
+use std::collections::{BTreeSet, HashSet};
+
+use rspirv::dr::Operand;
+use rspirv::spirv::{Capability, Dim, ImageFormat, Op};
+
+use crate::inspect;
+
+/// The value of an `OpTypeImage`'s "Sampled" operand for images used with a sampler.
+const SAMPLED: u32 = 1;
+
+/// The value of an `OpTypeImage`'s "Sampled" operand for storage images.
+const STORAGE: u32 = 2;
+
+/// Capabilities that implicitly declare other capabilities, as `capability => [implied, ...]`.
+const IMPLIED_CAPABILITIES: &[(Capability, &[Capability])] = &[
+    (Capability::ImageCubeArray, &[Capability::SampledCubeArray]),
+    (Capability::Image1D, &[Capability::Sampled1D]),
+    (Capability::ImageBuffer, &[Capability::SampledBuffer]),
+    (Capability::ImageRect, &[Capability::SampledRect]),
+];
+
+/// The storage image formats that need `StorageImageExtendedFormats`.
+const EXTENDED_FORMATS: &[ImageFormat] = &[
+    ImageFormat::Rg32f,
+    ImageFormat::Rg16f,
+    ImageFormat::R11fG11fB10f,
+    ImageFormat::R16f,
+    ImageFormat::Rgba16,
+    ImageFormat::Rgb10A2,
+    ImageFormat::Rg16,
+    ImageFormat::Rg8,
+    ImageFormat::R16,
+    ImageFormat::R8,
+    ImageFormat::Rgba16Snorm,
+    ImageFormat::Rg16Snorm,
+    ImageFormat::Rg8Snorm,
+    ImageFormat::R16Snorm,
+    ImageFormat::R8Snorm,
+    ImageFormat::Rg32i,
+    ImageFormat::Rg16i,
+    ImageFormat::Rg8i,
+    ImageFormat::R16i,
+    ImageFormat::R8i,
+    ImageFormat::Rgb10a2ui,
+    ImageFormat::Rg32ui,
+    ImageFormat::Rg16ui,
+    ImageFormat::Rg8ui,
+    ImageFormat::R16ui,
+    ImageFormat::R8ui,
+];
+
+/// The parts of an `OpTypeImage` that determine which capabilities it needs.
+struct ImageType {
+    /// Dimensionality, eg `Dim2D`.
+    dim: Dim,
+    /// Whether it's an array of images.
+    is_arrayed: bool,
+    /// Whether it's multisampled.
+    is_multisampled: bool,
+    /// `SAMPLED`, `STORAGE`, or 0 if only known at runtime.
+    sampled: u32,
+    /// The texel format, `Unknown` unless it's a storage image.
+    format: ImageFormat,
+}
+
+impl ImageType {
+    /// Read an `OpTypeImage` instruction.
+    fn parse(instruction: &rspirv::dr::Instruction) -> Option<Self> {
+        // The operands are: sampled type, dim, depth, arrayed, multisampled, sampled and format.
+        let literal = |index: usize| match instruction.operands.get(index) {
+            Some(&Operand::LiteralBit32(value)) => Some(value),
+            _ => None,
+        };
+        let Some(&Operand::Dim(dim)) = instruction.operands.get(1) else {
+            return None;
+        };
+        let Some(&Operand::ImageFormat(format)) = instruction.operands.get(6) else {
+            return None;
+        };
+
+        Some(Self {
+            dim,
+            is_arrayed: literal(3)? == 1,
+            is_multisampled: literal(4)? == 1,
+            sampled: literal(5)?,
+            format,
+        })
+    }
+
+    /// A description of the image for messages, eg "storage image (Dim2D, Rg16f)".
+    fn describe(&self) -> String {
+        let kind = if self.sampled == STORAGE {
+            "storage image"
+        } else {
+            "sampled image"
+        };
+        let arrayed = if self.is_arrayed { " array" } else { "" };
+        let multisampled = if self.is_multisampled {
+            ", multisampled"
+        } else {
+            ""
+        };
+        format!(
+            "{kind}{arrayed} ({:?}, {:?}{multisampled})",
+            self.dim, self.format
+        )
+    }
+
+    /// The capabilities that the image needs, as alternatives: any one of each set suffices.
+    fn required_capabilities(&self) -> Vec<&'static [Capability]> {
+        let is_storage = self.sampled == STORAGE;
+        let mut required: Vec<&'static [Capability]> = Vec::new();
+
+        match (self.dim, is_storage) {
+            (Dim::Dim1D, false) => required.push(&[Capability::Sampled1D]),
+            (Dim::Dim1D, true) => required.push(&[Capability::Image1D]),
+            (Dim::DimBuffer, false) => required.push(&[Capability::SampledBuffer]),
+            (Dim::DimBuffer, true) => required.push(&[Capability::ImageBuffer]),
+            (Dim::DimRect, false) => required.push(&[Capability::SampledRect]),
+            (Dim::DimRect, true) => required.push(&[Capability::ImageRect]),
+            (Dim::DimSubpassData, _) => required.push(&[Capability::InputAttachment]),
+            (Dim::DimCube, false) if self.is_arrayed => {
+                required.push(&[Capability::SampledCubeArray]);
+            }
+            (Dim::DimCube, true) if self.is_arrayed => required.push(&[Capability::ImageCubeArray]),
+            _ => (),
+        }
+
+        if is_storage && self.is_multisampled {
+            required.push(&[Capability::StorageImageMultisample]);
+        }
+        if self.is_multisampled && self.is_arrayed && self.sampled != SAMPLED {
+            required.push(&[Capability::ImageMSArray]);
+        }
+        if is_storage && EXTENDED_FORMATS.contains(&self.format) {
+            required.push(&[Capability::StorageImageExtendedFormats]);
+        }
+        if is_storage && self.format == ImageFormat::Unknown {
+            required.push(&[
+                Capability::StorageImageReadWithoutFormat,
+                Capability::StorageImageWriteWithoutFormat,
+            ]);
+        }
+
+        required
+    }
+}
+
+/// Fail if any of the module's image types need a capability that the module doesn't declare.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn check(module_path: &std::path::Path) -> anyhow::Result<()> {
+    let module = inspect::load(module_path)?;
+
+    let mut declared: HashSet<Capability> = inspect::capabilities(&module).into_iter().collect();
+    for &(capability, implied) in IMPLIED_CAPABILITIES {
+        if declared.contains(&capability) {
+            declared.extend(implied);
+        }
+    }
+
+    let mut mismatches = BTreeSet::new();
+    let images = module
+        .types_global_values
+        .iter()
+        .filter(|instruction| instruction.class.opcode == Op::TypeImage)
+        .filter_map(ImageType::parse);
+    for image in images {
+        for alternatives in image.required_capabilities() {
+            if !alternatives
+                .iter()
+                .any(|capability| declared.contains(capability))
+            {
+                let names: Vec<String> = alternatives
+                    .iter()
+                    .map(|capability| format!("`{capability:?}`"))
+                    .collect();
+                mismatches.insert(format!("{} needs {}", image.describe(), names.join(" or ")));
+            }
+        }
+    }
+
+    if !mismatches.is_empty() {
+        anyhow::bail!(
+            "Image resources need capabilities that the module doesn't declare, try \
+             `--capability`:\n  {}",
+            mismatches.into_iter().collect::<Vec<_>>().join("\n  ")
+        );
+    }
+
+    tracing::info!("  Module declares every capability its image resources need");
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+//! Make compiles use the Rust toolchain that `rust-gpu` needs, rather than whatever toolchain
+//! the shader crate or the environment would otherwise pick.
+
+/// This project's toolchain file, which always matches the toolchain `rust-gpu` is built with.
+const EMBEDDED_TOOLCHAIN: &str = include_str!("../rust-toolchain.toml");
+
+/// Overrides the embedded toolchain channel, for using a specific locally installed nightly
+/// without rebuilding.
+const TOOLCHAIN_OVERRIDE_VAR: &str = "RUST_GPU_TOOLCHAIN";
+
+/// The channel from the embedded `rust-toolchain.toml`.
+fn embedded_channel() -> anyhow::Result<String> {
+    let toolchain: toml::Table = toml::from_str(EMBEDDED_TOOLCHAIN)?;
+    toolchain
+        .get("toolchain")
+        .and_then(|table| table.get("channel"))
+        .and_then(toml::Value::as_str)
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| anyhow::anyhow!("Embedded `rust-toolchain.toml` has no channel"))
+}
+
+/// Fail with rustup's own error if the toolchain isn't installed.
+fn check_installed(channel: &str) -> anyhow::Result<()> {
+    let output = std::process::Command::new("rustup")
+        .args(["run", channel, "rustc", "--version"])
+        .output()
+        .map_err(|error| anyhow::anyhow!("Couldn't run rustup: {error}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Toolchain `{channel}` from `{TOOLCHAIN_OVERRIDE_VAR}` isn't usable: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Set `RUSTUP_TOOLCHAIN` for compiles, from `RUST_GPU_TOOLCHAIN` if it's set, otherwise from
+/// the embedded `rust-toolchain.toml`.
+pub fn ensure_rust_version() -> anyhow::Result<()> {
+    let channel = match std::env::var(TOOLCHAIN_OVERRIDE_VAR) {
+        Ok(channel) if !channel.trim().is_empty() => {
+            let channel = channel.trim().to_owned();
+            check_installed(&channel)?;
+            tracing::info!("Using Rust toolchain `{channel}` from `{TOOLCHAIN_OVERRIDE_VAR}`");
+            channel
+        }
+        _ => {
+            let channel = embedded_channel()?;
+            tracing::info!(
+                "Using Rust toolchain `{channel}` from the embedded `rust-toolchain.toml`"
+            );
+            channel
+        }
+    };
+    std::env::set_var("RUSTUP_TOOLCHAIN", channel);
+
+    Ok(())
+}
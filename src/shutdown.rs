@@ -0,0 +1,38 @@
+//! Stop compiling cleanly on SIGINT and SIGTERM, rather than part way through writing outputs.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::temp_output;
+use crate::trace;
+
+/// Held while a compile's result is being handled, so that shutting down waits for it.
+static HANDLING_RESULT: Mutex<()> = Mutex::new(());
+
+/// Exit with success on SIGINT or SIGTERM, once any compile result that's being handled, copying
+/// and validating it, has finished.
+pub fn install() -> anyhow::Result<()> {
+    ctrlc::set_handler(|| {
+        tracing::info!("Shutting down");
+        drop(
+            HANDLING_RESULT
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        temp_output::remove();
+        trace::flush();
+        #[expect(
+            clippy::exit,
+            reason = "The signal handler runs in its own thread, which can't return from `main`"
+        )]
+        std::process::exit(0);
+    })?;
+
+    Ok(())
+}
+
+/// Delay shutting down until the returned guard is dropped.
+pub fn defer() -> MutexGuard<'static, ()> {
+    HANDLING_RESULT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
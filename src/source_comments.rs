@@ -0,0 +1,123 @@
+//! Annotate cross-compiled WGSL with the Rust source locations that its functions came from.
+//! Locations are read from the `OpLine` debug instructions that `--spirv-metadata full` adds to
+//! the SPIR-V. `naga` doesn't carry them through to the WGSL it generates, so functions are
+//! matched up by name, and annotations are per function rather than per statement.
+
+use std::collections::HashMap;
+
+use rspirv::dr::Operand;
+use rspirv::spirv::Op;
+
+use crate::inspect;
+
+/// Add a `// file:line` comment above each WGSL function whose source location is known. `None`
+/// if the module has no source locations at all.
+pub fn annotate(spirv_path: &std::path::Path, wgsl: &str) -> anyhow::Result<Option<String>> {
+    let locations = function_locations(&inspect::load(spirv_path)?);
+    if locations.is_empty() {
+        return Ok(None);
+    }
+
+    let mut annotated = Vec::new();
+    for line in wgsl.lines() {
+        let trimmed = line.trim_start();
+        let maybe_location = trimmed.strip_prefix("fn ").and_then(|declaration| {
+            let name: String = declaration
+                .chars()
+                .take_while(|&character| is_identifier_char(character))
+                .collect();
+            locations.get(&name)
+        });
+        if let Some(location) = maybe_location {
+            let indent = line.strip_suffix(trimmed).unwrap_or_default();
+            annotated.push(format!("{indent}// {location}"));
+        }
+        annotated.push(line.to_owned());
+    }
+
+    let mut output = annotated.join("\n");
+    if wgsl.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(Some(output))
+}
+
+/// The source location, as `file:line`, of the first `OpLine` in each function, keyed by the
+/// function's names as `naga` would write them in WGSL.
+fn function_locations(module: &rspirv::dr::Module) -> HashMap<String, String> {
+    let files: HashMap<u32, &str> = module
+        .debug_string_source
+        .iter()
+        .filter(|instruction| instruction.class.opcode == Op::String)
+        .filter_map(
+            |instruction| match (instruction.result_id, instruction.operands.first()) {
+                (Some(id), Some(Operand::LiteralString(file))) => Some((id, file.as_str())),
+                _ => None,
+            },
+        )
+        .collect();
+
+    let mut names: Vec<(u32, &str)> = module
+        .debug_names
+        .iter()
+        .filter(|instruction| instruction.class.opcode == Op::Name)
+        .filter_map(|instruction| {
+            match (instruction.operands.first(), instruction.operands.get(1)) {
+                (Some(&Operand::IdRef(id)), Some(Operand::LiteralString(name))) => {
+                    Some((id, name.as_str()))
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    names.extend(module.entry_points.iter().filter_map(|instruction| {
+        match (instruction.operands.get(1), instruction.operands.get(2)) {
+            (Some(&Operand::IdRef(id)), Some(Operand::LiteralString(name))) => {
+                Some((id, name.as_str()))
+            }
+            _ => None,
+        }
+    }));
+
+    let mut locations = HashMap::new();
+    for function in &module.functions {
+        let Some(function_id) = function.def_id() else {
+            continue;
+        };
+        let maybe_location = function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .filter(|instruction| instruction.class.opcode == Op::Line)
+            .find_map(|instruction| {
+                match (instruction.operands.first(), instruction.operands.get(1)) {
+                    (Some(&Operand::IdRef(file_id)), Some(&Operand::LiteralBit32(line))) => {
+                        files.get(&file_id).map(|file| format!("{file}:{line}"))
+                    }
+                    _ => None,
+                }
+            });
+        let Some(location) = maybe_location else {
+            continue;
+        };
+
+        for &(_, name) in names.iter().filter(|&&(id, _)| id == function_id) {
+            locations.insert(sanitize(name), location.clone());
+        }
+    }
+
+    locations
+}
+
+/// Whether the character can appear in a WGSL identifier, as far as `naga` writes them.
+const fn is_identifier_char(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_'
+}
+
+/// Approximate how `naga` turns a SPIR-V name into a WGSL identifier, eg `shader::main` becomes
+/// `shadermain`.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .filter(|&character| is_identifier_char(character))
+        .collect()
+}
@@ -0,0 +1,103 @@
+//! Summarise each compile's errors and warnings, with counts and the first few of each, so that
+//! a long run of diagnostics is easier to digest. rustc's own diagnostics are rendered by the
+//! cargo that `spirv-builder` runs, so only the daemon's own errors and warnings are counted.
+
+use std::sync::Mutex;
+
+use tracing::field::{Field, Visit};
+use tracing::Level;
+
+/// How many messages of each severity to show in the summary.
+const SHOWN_PER_SEVERITY: usize = 3;
+
+/// Errors and warnings logged since they were last taken.
+static COLLECTED: Mutex<Diagnostics> = Mutex::new(Diagnostics {
+    errors: Vec::new(),
+    warnings: Vec::new(),
+});
+
+/// Diagnostic messages, by severity.
+pub struct Diagnostics {
+    /// Messages logged at the `ERROR` level.
+    pub errors: Vec<String>,
+    /// Messages logged at the `WARN` level.
+    pub warnings: Vec<String>,
+}
+
+/// Extracts the message of a log event.
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+/// A `tracing` layer that collects errors and warnings for the summary.
+pub struct Layer;
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Layer {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _context: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let level = *event.metadata().level();
+        if level != Level::ERROR && level != Level::WARN {
+            return;
+        }
+
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        if let Ok(mut collected) = COLLECTED.lock() {
+            if level == Level::ERROR {
+                collected.errors.push(visitor.0);
+            } else {
+                collected.warnings.push(visitor.0);
+            }
+        }
+    }
+}
+
+/// Take the errors and warnings logged since they were last taken, eg during the latest compile.
+pub fn take() -> Diagnostics {
+    COLLECTED.lock().map_or_else(
+        |_| Diagnostics {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        },
+        |mut collected| Diagnostics {
+            errors: core::mem::take(&mut collected.errors),
+            warnings: core::mem::take(&mut collected.warnings),
+        },
+    )
+}
+
+impl Diagnostics {
+    /// Log a summary of the errors and warnings.
+    pub fn report(&self) {
+        if self.errors.is_empty() && self.warnings.is_empty() {
+            return;
+        }
+
+        tracing::info!(
+            "Diagnostics: {} error(s), {} warning(s)",
+            self.errors.len(),
+            self.warnings.len()
+        );
+        for (severity, messages) in [("error", &self.errors), ("warning", &self.warnings)] {
+            for message in messages.iter().take(SHOWN_PER_SEVERITY) {
+                let first_line = message.lines().next().unwrap_or_default();
+                tracing::info!("  {severity}: {first_line}");
+            }
+            if messages.len() > SHOWN_PER_SEVERITY {
+                tracing::info!(
+                    "  ...and {} more {severity}(s)",
+                    messages.len().saturating_sub(SHOWN_PER_SEVERITY)
+                );
+            }
+        }
+    }
+}
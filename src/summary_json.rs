@@ -0,0 +1,69 @@
+//! Write a single JSON report of a one-shot `--validate-only` run, so that CI can parse the
+//! outcome of the whole invocation rather than its logs.
+
+use serde_json::json;
+use spirv_builder::{CompileResult, ModuleResult};
+
+use crate::atomic;
+
+/// The outcome of a one-shot compile and validation.
+pub struct Summary<'summary> {
+    /// The shader crate that was compiled.
+    pub crate_path: &'summary std::path::Path,
+    /// The target it was compiled for.
+    pub target: &'summary str,
+    /// How long compiling and checking the modules took.
+    pub duration: core::time::Duration,
+    /// The compiled modules, if compiling succeeded.
+    pub compile_result: Option<&'summary CompileResult>,
+    /// The outcome of validating and checking the compiled modules, or of compiling them if that
+    /// failed.
+    pub result: &'summary anyhow::Result<()>,
+}
+
+/// Describe a compiled module: its path, size and whether it's usable.
+fn artifact(entry_point: Option<&str>, path: &std::path::Path) -> serde_json::Value {
+    let maybe_bytes = std::fs::metadata(path).map(|metadata| metadata.len()).ok();
+    let status = match maybe_bytes {
+        None => "missing",
+        Some(0) => "empty",
+        Some(_) => "ok",
+    };
+    json!({
+        "entry_point": entry_point,
+        "path": path,
+        "bytes": maybe_bytes,
+        "status": status,
+    })
+}
+
+/// Overwrite the summary file with the outcome of the run.
+pub fn write(path: &std::path::Path, summary: &Summary<'_>) -> anyhow::Result<()> {
+    let artifacts: Vec<serde_json::Value> =
+        match summary.compile_result.map(|result| &result.module) {
+            None => Vec::new(),
+            Some(&ModuleResult::SingleModule(ref single)) => vec![artifact(None, single)],
+            Some(&ModuleResult::MultiModule(ref multi)) => multi
+                .iter()
+                .map(|(entry_point, module)| artifact(Some(entry_point.as_str()), module))
+                .collect(),
+        };
+
+    let error = summary
+        .result
+        .as_ref()
+        .err()
+        .map(|error| format!("{error:#}"));
+    let report = json!({
+        "success": summary.result.is_ok(),
+        "crate": summary.crate_path,
+        "target": summary.target,
+        "compiled": summary.compile_result.is_some(),
+        "duration_ms": summary.duration.as_millis(),
+        "validated": summary.compile_result.is_some() && summary.result.is_ok(),
+        "error": error,
+        "artifacts": artifacts,
+    });
+
+    atomic::write(path, serde_json::to_string_pretty(&report)?.as_bytes())
+}
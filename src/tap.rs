@@ -0,0 +1,37 @@
+//! Report one-shot compiles in the Test Anything Protocol, for test harnesses that consume TAP.
+//! See https://testanything.org
+
+use spirv_builder::{CompileResult, ModuleResult};
+
+/// The name of each test: one per compiled module, or one for the whole compile if it failed.
+pub fn test_names(
+    crate_name: &str,
+    target: &str,
+    maybe_compile_result: Option<&CompileResult>,
+) -> Vec<String> {
+    match maybe_compile_result.map(|compile_result| &compile_result.module) {
+        Some(&ModuleResult::MultiModule(ref multi)) => multi
+            .keys()
+            .map(|entry_point| format!("{crate_name} {target} {entry_point}"))
+            .collect(),
+        Some(&ModuleResult::SingleModule(_)) | None => vec![format!("{crate_name} {target}")],
+    }
+}
+
+/// Print a TAP plan, a line per test and a summary. Modules are checked together, so they all
+/// pass or fail together.
+#[expect(clippy::print_stdout, reason = "TAP is the flag's output")]
+pub fn report(names: &[String], is_success: bool) {
+    println!("1..{}", names.len());
+    for (index, name) in names.iter().enumerate() {
+        let status = if is_success { "ok" } else { "not ok" };
+        println!("{status} {} - {name}", index.saturating_add(1));
+    }
+
+    let (passed, failed) = if is_success {
+        (names.len(), 0)
+    } else {
+        (0, names.len())
+    };
+    println!("# passed {passed}, failed {failed}");
+}
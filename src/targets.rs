@@ -0,0 +1,101 @@
+//! The compile targets that rust-gpu supports, to catch typos in `--target` before they fail deep
+//! inside a compile, and to list them for `--print-targets`.
+
+/// Every target that rust-gpu can compile to, with the SPIR-V version and environment it's for.
+const TARGETS: &[(&str, &str)] = &[
+    ("spirv-unknown-spv1.0", "SPIR-V 1.0, for any environment"),
+    ("spirv-unknown-spv1.1", "SPIR-V 1.1, for any environment"),
+    ("spirv-unknown-spv1.2", "SPIR-V 1.2, for any environment"),
+    ("spirv-unknown-spv1.3", "SPIR-V 1.3, for any environment"),
+    ("spirv-unknown-spv1.4", "SPIR-V 1.4, for any environment"),
+    ("spirv-unknown-spv1.5", "SPIR-V 1.5, for any environment"),
+    ("spirv-unknown-vulkan1.0", "Vulkan 1.0, with SPIR-V 1.0"),
+    ("spirv-unknown-vulkan1.1", "Vulkan 1.1, with SPIR-V 1.3"),
+    (
+        "spirv-unknown-vulkan1.1spv1.4",
+        "Vulkan 1.1 with VK_KHR_spirv_1_4, with SPIR-V 1.4",
+    ),
+    ("spirv-unknown-vulkan1.2", "Vulkan 1.2, with SPIR-V 1.5"),
+    (
+        "spirv-unknown-opengl4.0",
+        "OpenGL 4.0 with ARB_gl_spirv, with SPIR-V 1.0",
+    ),
+    (
+        "spirv-unknown-opengl4.1",
+        "OpenGL 4.1 with ARB_gl_spirv, with SPIR-V 1.0",
+    ),
+    (
+        "spirv-unknown-opengl4.2",
+        "OpenGL 4.2 with ARB_gl_spirv, with SPIR-V 1.0",
+    ),
+    (
+        "spirv-unknown-opengl4.3",
+        "OpenGL 4.3 with ARB_gl_spirv, with SPIR-V 1.0",
+    ),
+    (
+        "spirv-unknown-opengl4.5",
+        "OpenGL 4.5 with ARB_gl_spirv, with SPIR-V 1.0",
+    ),
+    ("spirv-unknown-webgpu0", "WebGPU, with SPIR-V 1.0"),
+];
+
+/// Targets further than this many edits from any known target are too different to suggest one.
+const MAX_SUGGESTION_DISTANCE: usize = 4;
+
+/// The number of single character insertions, deletions or substitutions that turn one string
+/// into the other.
+fn edit_distance(from: &str, to: &str) -> usize {
+    let mut previous: Vec<usize> = (0..=to.chars().count()).collect();
+    for (row, from_char) in from.chars().enumerate() {
+        let mut current = vec![row.saturating_add(1)];
+        for (to_char, (&diagonal, &above)) in
+            to.chars().zip(previous.iter().zip(previous.iter().skip(1)))
+        {
+            let left = current.last().copied().unwrap_or_default();
+            let substitution = diagonal.saturating_add(usize::from(from_char != to_char));
+            current.push(
+                substitution
+                    .min(above.saturating_add(1))
+                    .min(left.saturating_add(1)),
+            );
+        }
+        previous = current;
+    }
+
+    previous.last().copied().unwrap_or_default()
+}
+
+/// Check that rust-gpu supports the target, suggesting the closest known target if it doesn't.
+pub fn check(target: &str) -> anyhow::Result<()> {
+    if TARGETS.iter().any(|&(known, _)| known == target) {
+        return Ok(());
+    }
+
+    let closest = TARGETS
+        .iter()
+        .map(|&(known, _)| (edit_distance(target, known), known))
+        .min_by_key(|&(distance, _)| distance)
+        .filter(|&(distance, _)| distance <= MAX_SUGGESTION_DISTANCE);
+    match closest {
+        Some((_, suggestion)) => {
+            anyhow::bail!("Unknown rust-gpu target '{target}', did you mean '{suggestion}'?")
+        }
+        None => anyhow::bail!(
+            "Unknown rust-gpu target '{target}', see `--print-targets` for the targets"
+        ),
+    }
+}
+
+/// Print every target with a description of what it's for.
+#[expect(clippy::print_stdout, reason = "Listing targets is the flag's output")]
+pub fn list() {
+    let width = TARGETS
+        .iter()
+        .map(|&(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+
+    for &(name, description) in TARGETS {
+        println!("{name:width$}  {description}");
+    }
+}
@@ -0,0 +1,45 @@
+//! Write outputs to a fresh temporary directory, for CI steps that consume them straight away,
+//! and remove it when the daemon exits.
+
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::Context as _;
+
+/// The temporary directory to remove on exit, if there is one.
+static TO_REMOVE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Create a uniquely named directory in the system's temporary directory. Unless `keep` is set,
+/// `remove` removes it.
+pub fn create(keep: bool) -> anyhow::Result<PathBuf> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let directory = std::env::temp_dir().join(format!("rust-gpu-{}-{nanos}", std::process::id()));
+    std::fs::create_dir(&directory).with_context(|| {
+        format!(
+            "Couldn't create temporary output directory '{}'",
+            directory.display()
+        )
+    })?;
+
+    if !keep {
+        *TO_REMOVE.lock().unwrap_or_else(PoisonError::into_inner) = Some(directory.clone());
+    }
+
+    Ok(directory)
+}
+
+/// Remove the temporary output directory, unless it's being kept.
+pub fn remove() {
+    let maybe_directory = TO_REMOVE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(directory) = maybe_directory {
+        if let Err(error) = std::fs::remove_dir_all(&directory) {
+            tracing::warn!("Couldn't remove temporary output directory {directory:?}: {error}");
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Emit a Chrome trace of the compilation phases, viewable in `chrome://tracing` or
+//! <https://ui.perfetto.dev>.
+
+use std::sync::{Mutex, OnceLock};
+
+use tracing_subscriber::registry::LookupSpan;
+
+/// Flushes the trace file. It's global so that it can be flushed after every compile.
+static FLUSH_GUARD: OnceLock<Mutex<tracing_chrome::FlushGuard>> = OnceLock::new();
+
+/// A `tracing` layer that writes spans to a Chrome trace file.
+pub fn layer<S>(path: &std::path::Path) -> tracing_chrome::ChromeLayer<S>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
+{
+    let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .file(path)
+        .include_args(true)
+        .build();
+    if FLUSH_GUARD.set(Mutex::new(guard)).is_err() {
+        tracing::warn!("Trace layer was already created");
+    }
+    layer
+}
+
+/// Write any buffered spans to the trace file. The daemon never exits cleanly, so the trace is
+/// flushed after every compile rather than on exit. Chrome's trace viewer doesn't need the file's
+/// closing bracket.
+pub fn flush() {
+    if let Some(guard) = FLUSH_GUARD.get() {
+        if let Ok(locked_guard) = guard.lock() {
+            locked_guard.flush();
+        }
+    }
+}
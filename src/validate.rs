@@ -1,28 +1,578 @@
 //! Validate a SPIR-V ".spv" binary.
 //! Thanks to @schell for the [code](https://github.com/Rust-GPU/rust-gpu/discussions/23#discussioncomment-10912823)
 
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
 use anyhow::Context;
 use naga::valid::ValidationFlags;
+use spirv_tools::val::Validator as _;
+
+use crate::{atomic, inspect, push_constants, source_comments};
+
+/// The validation capabilities `wgpu` gives a device without any optional features enabled.
+const WGPU_CAPABILITIES: naga::valid::Capabilities =
+    naga::valid::Capabilities::MULTISAMPLED_SHADING
+        .union(naga::valid::Capabilities::CUBE_ARRAY_TEXTURES);
+
+/// Capabilities of the runtime environment that a shader is validated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetEnv {
+    /// Vulkan 1.0, with the optional features that nearly every device supports.
+    Vulkan1_0,
+    /// Vulkan 1.1, which adds multiview and subgroups to the core.
+    Vulkan1_1,
+    /// Vulkan 1.2, which adds 64-bit types and descriptor indexing.
+    Vulkan1_2,
+    /// A default `wgpu` device, as on the web.
+    WebGpu,
+}
+
+impl TargetEnv {
+    /// Every environment, from the fewest capabilities to the most.
+    const ALL: [Self; 4] = [
+        Self::WebGpu,
+        Self::Vulkan1_0,
+        Self::Vulkan1_1,
+        Self::Vulkan1_2,
+    ];
+
+    /// The environment's name for `--validation-target-env`.
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Vulkan1_0 => "vulkan1.0",
+            Self::Vulkan1_1 => "vulkan1.1",
+            Self::Vulkan1_2 => "vulkan1.2",
+            Self::WebGpu => "webgpu",
+        }
+    }
+
+    /// The `naga` validation capabilities of the environment.
+    pub const fn capabilities(self) -> naga::valid::Capabilities {
+        use naga::valid::Capabilities;
+
+        let vulkan1_0 = Capabilities::PUSH_CONSTANT
+            .union(Capabilities::CLIP_DISTANCE)
+            .union(Capabilities::CULL_DISTANCE)
+            .union(Capabilities::MULTISAMPLED_SHADING)
+            .union(Capabilities::CUBE_ARRAY_TEXTURES);
+        let vulkan1_1 = vulkan1_0
+            .union(Capabilities::MULTIVIEW)
+            .union(Capabilities::SUBGROUP);
+        let vulkan1_2 = vulkan1_1
+            .union(Capabilities::FLOAT64)
+            .union(Capabilities::SHADER_INT64)
+            .union(Capabilities::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING)
+            .union(Capabilities::UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING);
+
+        match self {
+            Self::Vulkan1_0 => vulkan1_0,
+            Self::Vulkan1_1 => vulkan1_1,
+            Self::Vulkan1_2 => vulkan1_2,
+            Self::WebGpu => WGPU_CAPABILITIES,
+        }
+    }
+
+    /// The environment for `spirv-tools` to validate for. `wgpu` runs SPIR-V on Vulkan 1.0
+    /// semantics, so that stands in for WebGPU, which `spirv-tools` no longer supports.
+    const fn spirv_tools(self) -> spirv_tools::TargetEnv {
+        match self {
+            Self::Vulkan1_0 | Self::WebGpu => spirv_tools::TargetEnv::Vulkan_1_0,
+            Self::Vulkan1_1 => spirv_tools::TargetEnv::Vulkan_1_1,
+            Self::Vulkan1_2 => spirv_tools::TargetEnv::Vulkan_1_2,
+        }
+    }
+}
+
+/// `wgpu`'s default limits on compute workgroup sizes: x, y, z and the total invocations.
+const WGPU_MAX_WORKGROUP_SIZE: ([u32; 3], u32) = ([256, 256, 64], 256);
+
+/// Options for SPIR-V validation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationOption {
+    /// Only validate the generated SPIR-V module.
+    Spriv,
+    /// Also create a WGSL version of the SPIR-V module and validate that WGSL.
+    Wgsl,
+    /// Like `Wgsl`, but validate the WGSL how `wgpu` does in `create_shader_module`.
+    Wgpu,
+    /// Validate the SPIR-V module with the Khronos validator from `spirv-tools`, rather than
+    /// `naga`.
+    SpirvTools,
+}
 
-/// Validation entry point.
-pub fn validate(path: &std::path::PathBuf, is_validate_wgsl: bool) -> anyhow::Result<()> {
-    let (spirv_module, spirv_info, is_spirv_valid) = validate_spirv(path)?;
+/// How `naga`'s SPIR-V frontend treats parts of SPIR-V that it doesn't model. `naga` can only
+/// be told to skip unsupported capabilities: instructions that it can't translate are always
+/// errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownInstructions {
+    /// Parse the module anyway, in case the unsupported parts aren't used.
+    Skip,
+    /// Fail as soon as anything unsupported is declared.
+    Error,
+}
+
+/// Context added to validation errors, so that they can be told apart from other failures.
+#[derive(Debug)]
+pub struct ValidationFailed;
 
-    if is_validate_wgsl {
-        let wgsl_module = create_wgsl(path, &spirv_module, &spirv_info)?;
-        validate_wgsl(&wgsl_module)?;
+impl core::fmt::Display for ValidationFailed {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(formatter, "Validation failed")
+    }
+}
+
+/// Everything that configures validation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationSettings {
+    /// Which validations to run.
+    pub option: ValidationOption,
+    /// If set, also check that `naga` can generate SPIR-V from the module with this bounds
+    /// checking policy, as `wgpu` does at runtime.
+    pub bounds_check: Option<naga::proc::BoundsCheckPolicy>,
+    /// Annotate the generated WGSL with the Rust source locations of its functions.
+    pub source_comments: bool,
+    /// How to parse SPIR-V that `naga` doesn't model. `naga`'s own default if not set.
+    pub unknown_instructions: Option<UnknownInstructions>,
+    /// The environments whose capabilities to validate with, validating once for each. No
+    /// capabilities if there are none.
+    pub target_envs: Vec<TargetEnv>,
+    /// Where to write the cross-compiled WGSL: a directory, or a file. The temp directory if not
+    /// set.
+    pub wgsl_output: Option<std::path::PathBuf>,
+    /// Log the validation capabilities that modules need, and the target environments to
+    /// validate them with.
+    pub suggest_capabilities: bool,
+    /// How strictly to check the layout of push-constant blocks, if at all.
+    pub push_constant_layout: Option<push_constants::LayoutCheck>,
+    /// The most bytes that a push-constant block can be.
+    pub max_push_constant_bytes: Option<u32>,
+}
+
+impl ValidationSettings {
+    /// The environment of a single validation.
+    fn target_env(&self) -> Option<TargetEnv> {
+        self.target_envs.first().copied()
+    }
+
+    /// The same settings, but only for the one environment.
+    fn for_target_env(&self, target_env: TargetEnv) -> Self {
+        Self {
+            target_envs: vec![target_env],
+            ..self.clone()
+        }
+    }
+
+    /// Where to write the WGSL version of the SPIR-V module. Paths without an extension are
+    /// directories, in which the WGSL is named after the module.
+    fn wgsl_path(&self, spirv_path: &std::path::Path) -> anyhow::Result<std::path::PathBuf> {
+        if let Some(file) = self
+            .wgsl_output
+            .as_ref()
+            .filter(|path| !path.is_dir() && path.extension().is_some())
+        {
+            return Ok(file.clone());
+        }
+
+        let print_var_name = spirv_path
+            .file_stem()
+            .context("Couldn't get SPIR-V path file stem")?
+            .to_str()
+            .context("Couldn't get SPIR-V path to string")?
+            .replace('-', "_");
+        let dir = self.wgsl_output.clone().unwrap_or_else(std::env::temp_dir);
+        Ok(dir.join(print_var_name).with_extension("wgsl"))
+    }
+
+    /// Whether to suggest validation capabilities, which only `naga` validation has.
+    const fn suggests_capabilities(&self) -> bool {
+        self.suggest_capabilities && !matches!(self.option, ValidationOption::SpirvTools)
+    }
+
+    /// The capabilities to validate the SPIR-V, and any plain WGSL, with.
+    fn capabilities(&self) -> naga::valid::Capabilities {
+        self.target_env()
+            .map_or_else(naga::valid::Capabilities::empty, TargetEnv::capabilities)
+    }
+}
+
+/// Validation entry point. Returns the warnings that validating the module logged.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn validate(
+    path: &std::path::Path,
+    settings: &ValidationSettings,
+) -> anyhow::Result<Vec<String>> {
+    let mut warnings = Vec::new();
+    // Once for the module, as it doesn't depend on the target environment.
+    if settings.suggests_capabilities() {
+        suggest_capabilities(path, settings.unknown_instructions, &mut warnings)?;
+    }
+
+    validate_module(path, settings, &mut warnings)?;
+    Ok(warnings)
+}
+
+/// Log a warning about the module being validated, and keep it to return with the result.
+fn warn(warnings: &mut Vec<String>, message: String) {
+    tracing::warn!("{message}");
+    warnings.push(message);
+}
+
+/// Validate the module with the settings, for each of their target environments.
+fn validate_module(
+    path: &std::path::Path,
+    settings: &ValidationSettings,
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    if settings.target_envs.len() > 1 {
+        return validate_each_target_env(path, settings, warnings);
+    }
+
+    if matches!(settings.option, ValidationOption::SpirvTools) {
+        return validate_spirv_tools(path, settings.target_env());
+    }
+
+    let (spirv_module, spirv_info, is_spirv_valid) = validate_spirv(path, settings)?;
+
+    match settings.option {
+        ValidationOption::Spriv | ValidationOption::SpirvTools => (),
+        ValidationOption::Wgsl => {
+            let wgsl_module = create_wgsl(path, &spirv_module, &spirv_info, settings, warnings)?;
+            validate_wgsl(&wgsl_module, settings.capabilities())?;
+        }
+        ValidationOption::Wgpu => {
+            let wgsl_module = create_wgsl(path, &spirv_module, &spirv_info, settings, warnings)?;
+            validate_wgsl(&wgsl_module, WGPU_CAPABILITIES)?;
+            validate_wgpu_limits(&spirv_module)?;
+        }
     }
 
     if !is_spirv_valid {
         anyhow::bail!("SPIR-V validation error");
     }
 
+    if let Some(policy) = settings.bounds_check {
+        validate_bounds_checks(&spirv_module, &spirv_info, policy)?;
+    }
+
+    Ok(())
+}
+
+/// The settings and bytes of the modules that last passed validation, by path.
+static LAST_VALIDATED: Mutex<BTreeMap<PathBuf, (ValidationSettings, Vec<u8>)>> =
+    Mutex::new(BTreeMap::new());
+
+/// Like `validate()`, but skip a module that's byte-identical to the last one at the same path
+/// that passed validation with the same settings. A skipped module has no warnings.
+pub fn validate_if_changed(
+    path: &std::path::Path,
+    settings: &ValidationSettings,
+) -> anyhow::Result<Vec<String>> {
+    let bytes = std::fs::read(path)?;
+    let is_unchanged = LAST_VALIDATED.lock().is_ok_and(|last_validated| {
+        last_validated
+            .get(path)
+            .is_some_and(|(last_settings, last_bytes)| {
+                last_settings == settings && *last_bytes == bytes
+            })
+    });
+    if is_unchanged {
+        tracing::info!("  '{}' unchanged, validation skipped", path.display());
+        return Ok(Vec::new());
+    }
+
+    let warnings = validate(path, settings)?;
+    if let Ok(mut last_validated) = LAST_VALIDATED.lock() {
+        last_validated.insert(path.to_owned(), (settings.clone(), bytes));
+    }
+
+    Ok(warnings)
+}
+
+/// Validate once for each target environment, logging a matrix of which passed, and fail if any
+/// of them didn't.
+fn validate_each_target_env(
+    path: &std::path::Path,
+    settings: &ValidationSettings,
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let mut matrix = Vec::new();
+    let mut failures = Vec::new();
+    for &target_env in &settings.target_envs {
+        match validate_module(path, &settings.for_target_env(target_env), warnings) {
+            Ok(()) => matrix.push(format!("  {target_env:?}: pass")),
+            Err(error) => {
+                matrix.push(format!("  {target_env:?}: fail"));
+                failures.push(format!("{target_env:?}: {error:#}"));
+            }
+        }
+    }
+    tracing::info!("Validation by target environment:\n{}", matrix.join("\n"));
+
+    if !failures.is_empty() {
+        anyhow::bail!(
+            "Validation failed for {} of {} target environments:\n  {}",
+            failures.len(),
+            settings.target_envs.len(),
+            failures.join("\n  ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Describe the steps that `validate()` takes with the settings, without taking them.
+pub fn plan(settings: &ValidationSettings) -> Vec<String> {
+    let mut steps = Vec::new();
+    if settings.suggests_capabilities() {
+        steps.push(
+            "work out the validation capabilities the SPIR-V needs and suggest a target \
+             environment"
+                .to_owned(),
+        );
+    }
+    steps.extend(plan_module(settings));
+    steps
+}
+
+/// Describe the steps that `validate_module()` takes with the settings.
+fn plan_module(settings: &ValidationSettings) -> Vec<String> {
+    if settings.target_envs.len() > 1 {
+        return settings
+            .target_envs
+            .iter()
+            .flat_map(|&target_env| {
+                plan_module(&settings.for_target_env(target_env))
+                    .into_iter()
+                    .map(move |step| format!("for {target_env:?}, {step}"))
+            })
+            .collect();
+    }
+
+    if matches!(settings.option, ValidationOption::SpirvTools) {
+        let target_env = settings.target_env().map_or_else(
+            || "its default environment".to_owned(),
+            |target_env| format!("{:?}", target_env.spirv_tools()),
+        );
+        return vec![format!(
+            "validate the SPIR-V with spirv-tools for {target_env}"
+        )];
+    }
+
+    let parse = settings.unknown_instructions.map_or_else(
+        || "parse the SPIR-V with naga".to_owned(),
+        |unknown| {
+            format!("parse the SPIR-V with naga, with {unknown:?} for unsupported capabilities")
+        },
+    );
+    let mut steps = vec![format!(
+        "{parse} and validate it with {:?} and {}",
+        ValidationFlags::default(),
+        describe_capabilities(settings.capabilities())
+    )];
+    if let Some(layout_check) = settings.push_constant_layout {
+        steps.push(match layout_check {
+            push_constants::LayoutCheck::Report => {
+                "log the layout of push-constant blocks".to_owned()
+            }
+            push_constants::LayoutCheck::Strict => {
+                "log the layout of push-constant blocks, and check they have no gaps".to_owned()
+            }
+        });
+    }
+    if let Some(max_bytes) = settings.max_push_constant_bytes {
+        steps.push(format!(
+            "check push-constant blocks are no bigger than {max_bytes} bytes"
+        ));
+    }
+
+    let wgsl_path = settings
+        .wgsl_path(std::path::Path::new("[module name].spv"))
+        .unwrap_or_else(|_| std::env::temp_dir().join("[module name].wgsl"));
+    let annotate = "annotate the WGSL with the Rust source locations of its functions";
+    match settings.option {
+        ValidationOption::Spriv | ValidationOption::SpirvTools => (),
+        ValidationOption::Wgsl => {
+            if settings.source_comments {
+                steps.push(annotate.to_owned());
+            }
+            steps.push(format!(
+                "write the WGSL version to '{}'",
+                wgsl_path.display()
+            ));
+            steps.push(format!(
+                "parse the WGSL and validate it with {}",
+                describe_capabilities(settings.capabilities())
+            ));
+        }
+        ValidationOption::Wgpu => {
+            if settings.source_comments {
+                steps.push(annotate.to_owned());
+            }
+            steps.push(format!(
+                "write the WGSL version to '{}'",
+                wgsl_path.display()
+            ));
+            steps.push(format!(
+                "parse the WGSL and validate it with {WGPU_CAPABILITIES:?}"
+            ));
+            let ([max_x, max_y, max_z], max_invocations) = WGPU_MAX_WORKGROUP_SIZE;
+            steps.push(format!(
+                "check compute workgroup sizes are within ({max_x}, {max_y}, {max_z}) and \
+                 {max_invocations} invocations"
+            ));
+        }
+    }
+
+    if let Some(policy) = settings.bounds_check {
+        steps.push(format!(
+            "generate SPIR-V with naga using the {policy:?} bounds checking policy"
+        ));
+    }
+
+    steps
+}
+
+/// Describe validation capabilities for the validation plan.
+fn describe_capabilities(capabilities: naga::valid::Capabilities) -> String {
+    if capabilities.is_empty() {
+        "no capabilities".to_owned()
+    } else {
+        format!("{capabilities:?}")
+    }
+}
+
+/// Parse a SPIR-V binary into a `naga` module.
+pub fn parse_spirv(bytes: &[u8]) -> anyhow::Result<naga::Module> {
+    parse_spirv_with(bytes, None)
+}
+
+/// Parse and validate a SPIR-V module for translating with `naga`'s backends, which need the
+/// validation's info. Validates with all capabilities, as the module has already been compiled.
+pub fn parse_for_backends(
+    path: &std::path::Path,
+) -> anyhow::Result<(naga::Module, naga::valid::ModuleInfo)> {
+    let module = parse_spirv(&std::fs::read(path)?)?;
+    let info =
+        naga::valid::Validator::new(ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+            .with_context(|| format!("Couldn't validate '{}' for translation", path.display()))?;
+    Ok((module, info))
+}
+
+/// Parse a SPIR-V binary into a `naga` module, choosing how to treat what `naga` doesn't model.
+fn parse_spirv_with(
+    bytes: &[u8],
+    unknown_instructions: Option<UnknownInstructions>,
+) -> anyhow::Result<naga::Module> {
+    let mut opts = naga::front::spv::Options::default();
+    match unknown_instructions {
+        Some(UnknownInstructions::Skip) => opts.strict_capabilities = false,
+        Some(UnknownInstructions::Error) => opts.strict_capabilities = true,
+        None => (),
+    }
+    match naga::front::spv::parse_u8_slice(bytes, &opts) {
+        Ok(module) => Ok(module),
+        Err(error) => anyhow::bail!(error),
+    }
+}
+
+/// The `naga` validation capabilities that the module can't be validated without. Found by
+/// withholding each capability in turn.
+pub fn required_capabilities(module: &naga::Module) -> anyhow::Result<naga::valid::Capabilities> {
+    let all = naga::valid::Capabilities::all();
+    if let Err(error) =
+        naga::valid::Validator::new(ValidationFlags::default(), all).validate(module)
+    {
+        anyhow::bail!(
+            "Module is invalid even with all validation capabilities enabled: {}",
+            error.emit_to_string("")
+        );
+    }
+
+    Ok(all
+        .iter()
+        .filter(|&capability| {
+            naga::valid::Validator::new(ValidationFlags::default(), all.difference(capability))
+                .validate(module)
+                .is_err()
+        })
+        .fold(
+            naga::valid::Capabilities::empty(),
+            |required, capability| required | capability,
+        ))
+}
+
+/// Validate the SPIR-V module with the Khronos validator, which is what Vulkan's validation
+/// layers use, so it's more authoritative than `naga`'s incomplete SPIR-V frontend.
+fn validate_spirv_tools(
+    path: &std::path::Path,
+    target_env: Option<TargetEnv>,
+) -> anyhow::Result<()> {
+    tracing::info!("validating with spirv-tools");
+    tracing::info!("  reading '{}'", path.display());
+    let words = inspect::words(&std::fs::read(path)?)?;
+
+    let validator = spirv_tools::val::create(target_env.map(TargetEnv::spirv_tools));
+    validator
+        .validate(&words, None)
+        .map_err(|error| anyhow::anyhow!("SPIR-V validation error: {error}"))?;
+    tracing::info!("  SPIR-V validated");
+
+    Ok(())
+}
+
+/// Log the validation capabilities that the module needs, and the least capable target
+/// environment to validate it with, to save finding them by trial and error.
+fn suggest_capabilities(
+    path: &std::path::Path,
+    unknown_instructions: Option<UnknownInstructions>,
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let module = parse_spirv_with(&std::fs::read(path)?, unknown_instructions)?;
+    let required = match required_capabilities(&module) {
+        Ok(required) => required,
+        Err(error) => {
+            warn(
+                warnings,
+                format!("Couldn't work out the validation capabilities needed: {error:?}"),
+            );
+            return Ok(());
+        }
+    };
+    if required.is_empty() {
+        tracing::info!("  No validation capabilities needed, so no `--validation-target-env`");
+        return Ok(());
+    }
+
+    let names: Vec<&str> = required.iter_names().map(|(name, _)| name).collect();
+    let maybe_target_env = TargetEnv::ALL
+        .into_iter()
+        .find(|target_env| target_env.capabilities().contains(required));
+    match maybe_target_env {
+        Some(target_env) => tracing::info!(
+            "  Needs validation capabilities {}, suggested: `--validation-target-env {}`",
+            names.join(", "),
+            target_env.name()
+        ),
+        None => warn(
+            warnings,
+            format!(
+                "  Needs validation capabilities {}, which no `--validation-target-env` has",
+                names.join(", ")
+            ),
+        ),
+    }
+
     Ok(())
 }
 
 /// Validate the SPIR-V binary.
+#[tracing::instrument(level = "debug", skip_all)]
 fn validate_spirv(
-    path: &std::path::PathBuf,
+    path: &std::path::Path,
+    settings: &ValidationSettings,
 ) -> anyhow::Result<(naga::Module, naga::valid::ModuleInfo, bool)> {
     let is_spirv_valid;
 
@@ -32,17 +582,11 @@ fn validate_spirv(
     let bytes = std::fs::read(path)?;
     tracing::info!("  {:0.2}k bytes read", bytes.len() as f32 / 1000.0);
 
-    let opts = naga::front::spv::Options::default();
-    let spirv_module = match naga::front::spv::parse_u8_slice(&bytes, &opts) {
-        Ok(module) => module,
-        Err(error) => anyhow::bail!(error),
-    };
+    let spirv_module = parse_spirv_with(&bytes, settings.unknown_instructions)?;
     tracing::info!("  SPIR-V parsed");
 
-    let mut spirv_validator = naga::valid::Validator::new(
-        ValidationFlags::default(),
-        naga::valid::Capabilities::empty(),
-    );
+    let mut spirv_validator =
+        naga::valid::Validator::new(ValidationFlags::default(), settings.capabilities());
     let spirv_info = match spirv_validator.validate(&spirv_module) {
         Ok(info) => {
             is_spirv_valid = true;
@@ -69,42 +613,68 @@ fn validate_spirv(
         }
     };
 
+    if settings.push_constant_layout.is_some() || settings.max_push_constant_bytes.is_some() {
+        push_constants::check(
+            &spirv_module,
+            settings.push_constant_layout,
+            settings.max_push_constant_bytes,
+        )?;
+    }
+
     Ok((spirv_module, spirv_info, is_spirv_valid))
 }
 
 /// Convert the SPIR-V module to WGSL using `naga`.
+#[tracing::instrument(level = "debug", skip_all)]
 fn create_wgsl(
     path: &std::path::Path,
     spirv_module: &naga::Module,
     spirv_info: &naga::valid::ModuleInfo,
+    settings: &ValidationSettings,
+    warnings: &mut Vec<String>,
 ) -> anyhow::Result<String> {
-    let wgsl = naga::back::wgsl::write_string(
+    let mut wgsl = naga::back::wgsl::write_string(
         spirv_module,
         spirv_info,
         naga::back::wgsl::WriterFlags::empty(),
     )?;
     tracing::info!("  output WGSL generated");
 
-    let print_var_name = path
-        .file_stem()
-        .context("Couldn't get SPIR-V path file stem")?
-        .to_str()
-        .context("Couldn't get SPIR-V path to string")?
-        .replace('-', "_");
+    if settings.source_comments {
+        match source_comments::annotate(path, &wgsl)? {
+            Some(annotated) => {
+                wgsl = annotated;
+                tracing::info!("  output WGSL annotated with source locations");
+            }
+            None => warn(
+                warnings,
+                format!(
+                    "No source locations found in '{}', was it compiled with \
+                     `--spirv-metadata full`?",
+                    path.display()
+                ),
+            ),
+        }
+    }
 
-    let dir = std::env::temp_dir();
-    std::fs::create_dir_all(&dir)?;
-    let output_path = dir.join(print_var_name).with_extension("wgsl");
+    let output_path = settings.wgsl_path(path)?;
+    if let Some(dir) = output_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(dir)?;
+    }
     tracing::info!("writing WGSL to '{}'", output_path.display());
 
-    std::fs::write(&output_path, &wgsl)?;
+    atomic::write(&output_path, wgsl.as_bytes())?;
     tracing::info!("  wrote generated WGSL to {}", output_path.display());
 
     Ok(wgsl)
 }
 
 /// Validate the cross-compiled WGSL version of the SPIR-V module.
-fn validate_wgsl(wgsl_string: &str) -> anyhow::Result<()> {
+#[tracing::instrument(level = "debug", skip_all)]
+fn validate_wgsl(wgsl_string: &str, capabilities: naga::valid::Capabilities) -> anyhow::Result<()> {
     let wgsl_module = match naga::front::wgsl::parse_str(wgsl_string) {
         Ok(module) => module,
         Err(error) => {
@@ -112,10 +682,7 @@ fn validate_wgsl(wgsl_string: &str) -> anyhow::Result<()> {
         }
     };
     tracing::info!("  output WGSL parsed");
-    let mut wgsl_validator = naga::valid::Validator::new(
-        ValidationFlags::default(),
-        naga::valid::Capabilities::empty(),
-    );
+    let mut wgsl_validator = naga::valid::Validator::new(ValidationFlags::default(), capabilities);
     let _info = match wgsl_validator.validate(&wgsl_module) {
         Ok(info) => info,
         Err(error) => {
@@ -126,3 +693,50 @@ fn validate_wgsl(wgsl_string: &str) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Check that `naga` can generate SPIR-V from the module when applying the bounds checking
+/// policy to every kind of access, as `wgpu` does when it creates a shader module at runtime.
+#[tracing::instrument(level = "debug", skip_all)]
+fn validate_bounds_checks(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    policy: naga::proc::BoundsCheckPolicy,
+) -> anyhow::Result<()> {
+    let options = naga::back::spv::Options {
+        bounds_check_policies: naga::proc::BoundsCheckPolicies {
+            index: policy,
+            buffer: policy,
+            image_load: policy,
+            binding_array: policy,
+        },
+        ..Default::default()
+    };
+    naga::back::spv::write_vec(module, info, &options, None)
+        .with_context(|| format!("Couldn't apply the {policy:?} bounds checking policy"))?;
+    tracing::info!("  {policy:?} bounds checks validated");
+
+    Ok(())
+}
+
+/// Check the module against the default limits of a `wgpu` device.
+fn validate_wgpu_limits(module: &naga::Module) -> anyhow::Result<()> {
+    let ([max_x, max_y, max_z], max_invocations) = WGPU_MAX_WORKGROUP_SIZE;
+    for entry_point in &module.entry_points {
+        if entry_point.stage != naga::ShaderStage::Compute {
+            continue;
+        }
+
+        let [x, y, z] = entry_point.workgroup_size;
+        let invocations = x.saturating_mul(y).saturating_mul(z);
+        if x > max_x || y > max_y || z > max_z || invocations > max_invocations {
+            anyhow::bail!(
+                "Entry point `{}` has a workgroup size of ({x}, {y}, {z}), which exceeds wgpu's \
+                 default limits of ({max_x}, {max_y}, {max_z}) and {max_invocations} invocations",
+                entry_point.name
+            );
+        }
+    }
+    tracing::info!("  wgpu limits validated");
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! Watch a directory of SPIR-V binaries produced by some other tool, and validate each one
+//! whenever it's created or changed. Nothing is compiled.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use anyhow::Context as _;
+use notify::Watcher as _;
+
+use crate::validate::{validate, ValidationSettings};
+
+/// How long to wait for more changes after the first, so that a file that's written in several
+/// chunks is only validated once.
+const DEBOUNCE: core::time::Duration = core::time::Duration::from_millis(200);
+
+/// Whether the path is a SPIR-V binary.
+fn is_spirv(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "spv") && path.is_file()
+}
+
+/// Validate a binary, logging rather than returning the result, as the watcher carries on either
+/// way.
+fn validate_and_log(path: &Path, settings: &ValidationSettings) {
+    let _span = tracing::info_span!("validate", path = %path.display()).entered();
+    match validate(path, settings) {
+        Ok(_warnings) => tracing::info!("✅ {} is valid", path.display()),
+        Err(error) => tracing::error!("{}: {error:?}", path.display()),
+    }
+}
+
+/// Validate every binary already in the directory, then every binary that's created or changed.
+/// Only returns if the watcher fails.
+pub fn run(directory: &Path, settings: &ValidationSettings) -> anyhow::Result<()> {
+    let existing = std::fs::read_dir(directory)
+        .with_context(|| format!("Couldn't read directory '{}'", directory.display()))?;
+    for entry in existing {
+        let path = entry?.path();
+        if is_spirv(&path) {
+            validate_and_log(&path, settings);
+        }
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(changed) = event {
+            if matches!(
+                changed.kind,
+                notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+            ) {
+                for path in changed.paths {
+                    // The receiver only goes away when we're exiting anyway.
+                    drop(sender.send(path));
+                }
+            }
+        }
+    })?;
+    watcher.watch(directory, notify::RecursiveMode::Recursive)?;
+    tracing::info!("Watching {} for SPIR-V changes", directory.display());
+
+    while let Ok(first) = receiver.recv() {
+        let mut changed: BTreeSet<PathBuf> = BTreeSet::from([first]);
+        while let Ok(path) = receiver.recv_timeout(DEBOUNCE) {
+            changed.insert(path);
+        }
+
+        for path in changed.iter().filter(|path| is_spirv(path)) {
+            validate_and_log(path, settings);
+        }
+    }
+
+    anyhow::bail!("File watcher stopped unexpectedly")
+}
@@ -0,0 +1,431 @@
+//! Helpers for controlling how the daemon reacts to watch-triggered compiles.
+
+use core::hash::{Hash as _, Hasher as _};
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use notify::Watcher as _;
+
+use crate::process_tree;
+
+/// The rolling window over which compiles are counted.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits the number of compiles within a rolling one minute window.
+pub struct RateLimiter {
+    /// Maximum number of compiles allowed in the window.
+    max_compiles: usize,
+    /// When recent compiles happened, oldest first.
+    compiles: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter allowing `max_compiles` compiles per minute.
+    pub fn new(max_compiles: core::num::NonZeroUsize) -> Self {
+        Self {
+            max_compiles: max_compiles.get(),
+            compiles: VecDeque::new(),
+        }
+    }
+
+    /// Record a compile. If that reaches the limit, block the watcher until the oldest compile
+    /// leaves the window, so that no more compiles can start in the meantime.
+    pub fn throttle(&mut self) {
+        let now = Instant::now();
+        while self
+            .compiles
+            .front()
+            .is_some_and(|compiled_at| now.duration_since(*compiled_at) >= RATE_LIMIT_WINDOW)
+        {
+            self.compiles.pop_front();
+        }
+        self.compiles.push_back(now);
+
+        if self.compiles.len() < self.max_compiles {
+            return;
+        }
+
+        if let Some(oldest) = self.compiles.front() {
+            let pause = RATE_LIMIT_WINDOW.saturating_sub(now.duration_since(*oldest));
+            tracing::warn!(
+                "Reached {} compiles per minute, pausing compilation for {}s",
+                self.max_compiles,
+                pause.as_secs()
+            );
+            std::thread::sleep(pause);
+        }
+    }
+}
+
+/// What to do when a compile takes longer than `--timeout`.
+#[derive(Clone, Copy, Debug)]
+pub enum TimeoutAction {
+    /// Log a warning but let the compile finish.
+    Warn,
+    /// Abort the compile, by killing its `cargo` process tree. The daemon then waits for the next
+    /// change before compiling again.
+    Kill,
+}
+
+/// Enforces a timeout on a compile that's in progress. Dropping the watchdog disarms it.
+pub struct Watchdog {
+    /// Set once the compile has finished.
+    is_finished: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+    /// Start timing a compile.
+    pub fn start(timeout: Duration, action: TimeoutAction) -> Self {
+        let is_finished = Arc::new(AtomicBool::new(false));
+        let is_finished_for_thread = Arc::clone(&is_finished);
+
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            if is_finished_for_thread.load(Ordering::Relaxed) {
+                return;
+            }
+
+            match action {
+                TimeoutAction::Warn => {
+                    tracing::warn!(
+                        "Compile is taking longer than {}s, still waiting",
+                        timeout.as_secs()
+                    );
+                }
+                TimeoutAction::Kill => {
+                    tracing::warn!(
+                        "Compile took longer than {}s, killing it",
+                        timeout.as_secs()
+                    );
+                    process_tree::kill_compiles();
+                }
+            }
+        });
+
+        Self { is_finished }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.is_finished.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Periodically runs a cheap, no-change, compile while the watcher is idle, so that the OS doesn't
+/// evict cargo's incremental artifacts and make the next real compile slow.
+#[derive(Clone)]
+pub struct Keepalive {
+    /// How long the watcher has to be idle before a keepalive compile.
+    interval: Duration,
+    /// When the last compile, real or keepalive, finished.
+    last_compile: Arc<Mutex<Instant>>,
+}
+
+impl Keepalive {
+    /// Create a keepalive that compiles after `interval` without any other compiles.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_compile: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Record that a compile has just finished.
+    pub fn touch(&self) {
+        if let Ok(mut last_compile) = self.last_compile.lock() {
+            *last_compile = Instant::now();
+        }
+    }
+
+    /// How long since the last compile finished.
+    fn idle_for(&self) -> Duration {
+        self.last_compile
+            .lock()
+            .map_or(Duration::ZERO, |last_compile| last_compile.elapsed())
+    }
+
+    /// Start running `compile` in the background whenever the watcher has been idle for the
+    /// interval.
+    pub fn start<F: FnMut() + Send + 'static>(&self, mut compile: F) {
+        let keepalive = self.clone();
+        std::thread::spawn(move || loop {
+            let idle_for = keepalive.idle_for();
+            if idle_for < keepalive.interval {
+                std::thread::sleep(keepalive.interval.saturating_sub(idle_for));
+                continue;
+            }
+
+            tracing::debug!("Running keepalive compile");
+            compile();
+            keepalive.touch();
+        });
+    }
+}
+
+/// Names of directories whose contents are never sources: build output, and JavaScript
+/// dependencies of any web frontend that lives alongside the shaders. Hidden directories, like
+/// `.git/`, are skipped too.
+const IGNORED_DIRECTORIES: &[&str] = &["target", "node_modules"];
+
+/// Files in a package, besides Rust sources, that change how it compiles.
+const PACKAGE_FILES: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+];
+
+/// The files that compiles depend on: the Rust sources and Cargo files of the shader crate and
+/// its local dependencies, and every file in the extra watch paths. Anything else in the
+/// packages' directories, like the compiled modules when they're copied into the shader crate,
+/// isn't a source, so that writing it doesn't trigger another compile.
+#[derive(Clone)]
+pub struct Sources {
+    /// The directories of the shader crate and its local dependencies.
+    packages: Vec<PathBuf>,
+    /// Directories in which every file is a source, eg external shader includes.
+    directories: Vec<PathBuf>,
+}
+
+impl Sources {
+    /// The sources in the package directories, and in the other directories.
+    pub fn new(packages: &[PathBuf], directories: &[PathBuf]) -> Self {
+        let canonicalize = |path: &PathBuf| path.canonicalize().unwrap_or_else(|_| path.clone());
+        Self {
+            packages: packages.iter().map(canonicalize).collect(),
+            directories: directories.iter().map(canonicalize).collect(),
+        }
+    }
+
+    /// Every directory that sources are in.
+    fn roots(&self) -> impl Iterator<Item = &PathBuf> {
+        self.packages.iter().chain(&self.directories)
+    }
+
+    /// Whether a change to the path could change the compiled shader. Paths without an
+    /// extension count, as they may be directories that were removed or renamed.
+    fn contains(&self, path: &Path) -> bool {
+        let is_in = |root: &PathBuf| {
+            path.strip_prefix(root)
+                .is_ok_and(|relative| !relative.iter().any(is_ignored))
+        };
+        let is_package_file = path.extension().map_or(true, |extension| extension == "rs")
+            || path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| PACKAGE_FILES.contains(&name));
+
+        self.directories.iter().any(is_in) || (is_package_file && self.packages.iter().any(is_in))
+    }
+
+    /// Hash the paths and contents of every source file. The hash is only compared within a
+    /// single run of the daemon.
+    pub fn hash(&self) -> anyhow::Result<u64> {
+        let mut files = std::collections::BTreeSet::new();
+        let mut pending: Vec<PathBuf> = self.roots().cloned().collect();
+        while let Some(directory) = pending.pop() {
+            for entry in std::fs::read_dir(&directory)
+                .with_context(|| format!("Couldn't read '{}'", directory.display()))?
+            {
+                let path = entry?.path();
+                if path.is_dir() {
+                    if !path.file_name().is_some_and(is_ignored) {
+                        pending.push(path);
+                    }
+                } else if self.contains(&path) {
+                    files.insert(path);
+                }
+            }
+        }
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for file in files {
+            file.hash(&mut hasher);
+            std::fs::read(&file)
+                .with_context(|| format!("Couldn't read '{}'", file.display()))?
+                .hash(&mut hasher);
+        }
+
+        Ok(hasher.finish())
+    }
+}
+
+/// Whether a file or directory name is hidden or one of the `IGNORED_DIRECTORIES`, so nothing in
+/// it is a source.
+fn is_ignored(name: &std::ffi::OsStr) -> bool {
+    let name = name.to_string_lossy();
+    name.starts_with('.') || IGNORED_DIRECTORIES.contains(&name.as_ref())
+}
+
+/// A running `debounced` watch. Dropping it stops the watch, though a compile in progress
+/// finishes first.
+pub struct Debounced {
+    /// The watcher, which stops sending changes once it's dropped.
+    _watcher: notify::RecommendedWatcher,
+    /// Set when the watch is stopped on purpose, so that stopping isn't reported as a failure.
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl Drop for Debounced {
+    fn drop(&mut self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Watch the sources, and call `compile` in the background once changes to them have stopped for
+/// `debounce`, so that a burst of saves only compiles once. A compile in progress isn't
+/// interrupted, changes made during it are coalesced into the next compile. Descriptions of the
+/// watcher's failures are sent to `failures`.
+pub fn debounced<F: FnMut() + Send + 'static>(
+    sources: &Sources,
+    debounce: Duration,
+    failures: mpsc::Sender<String>,
+    mut compile: F,
+) -> anyhow::Result<Debounced> {
+    let (sender, receiver) = mpsc::channel();
+    let event_sources = sources.clone();
+    let event_failures = failures.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let failure = match event {
+            Ok(changed) if changed.need_rescan() => {
+                "file watcher missed events and needs a rescan".to_owned()
+            }
+            Ok(changed) => {
+                // Events without paths could be about anything.
+                let is_source = changed.paths.is_empty()
+                    || changed
+                        .paths
+                        .iter()
+                        .any(|path| event_sources.contains(path));
+                if is_source && !changed.kind.is_access() {
+                    // The receiver only goes away once the watch has been stopped.
+                    drop(sender.send(()));
+                }
+                return;
+            }
+            Err(error) => format!("file watcher error: {error}"),
+        };
+        // The receiver only goes away when the health is no longer being monitored.
+        drop(event_failures.send(failure));
+    })?;
+    for root in sources.roots() {
+        watcher.watch(root, notify::RecursiveMode::Recursive)?;
+    }
+
+    let is_stopped = Arc::new(AtomicBool::new(false));
+    let is_stopped_for_thread = Arc::clone(&is_stopped);
+    std::thread::spawn(move || {
+        while receiver.recv().is_ok() {
+            while receiver.recv_timeout(debounce).is_ok() {}
+            compile();
+        }
+        if !is_stopped_for_thread.load(Ordering::Relaxed) {
+            drop(failures.send("file watcher stopped unexpectedly".to_owned()));
+        }
+    });
+
+    Ok(Debounced {
+        _watcher: watcher,
+        is_stopped,
+    })
+}
+
+/// The hash of the watched sources at the last successful compile.
+static LAST_COMPILED_SOURCES: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Whether the sources are the same as at the last successful compile.
+pub fn is_unchanged_since_compile(hash: u64) -> bool {
+    LAST_COMPILED_SOURCES
+        .lock()
+        .is_ok_and(|last| *last == Some(hash))
+}
+
+/// Record the hash of the sources that were just compiled successfully.
+pub fn record_compiled_sources(hash: u64) {
+    if let Ok(mut last) = LAST_COMPILED_SOURCES.lock() {
+        *last = Some(hash);
+    }
+}
+
+/// Read the directories to watch from a file with one per line. Blank lines and lines starting
+/// with `#` are ignored, and relative directories are relative to the file.
+pub fn read_paths(file: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let contents = std::fs::read_to_string(file)
+        .with_context(|| format!("Couldn't read watch paths from '{}'", file.display()))?;
+    let base = file.parent().unwrap_or_else(|| Path::new("."));
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let path = base.join(line);
+            if !path.is_dir() {
+                anyhow::bail!(
+                    "Watch path '{line}' in '{}' isn't a directory",
+                    file.display()
+                );
+            }
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Where panics in background threads are reported, for the current `WatchHealth`.
+static PANIC_SENDER: Mutex<Option<mpsc::Sender<String>>> = Mutex::new(None);
+
+/// Ensures the panic hook is only installed once.
+static PANIC_HOOK: Once = Once::new();
+
+/// Detects when the watch has likely stopped delivering changes: when the watcher reports that
+/// it failed, and when a background thread panics.
+pub struct WatchHealth {
+    /// Where failures are reported.
+    sender: mpsc::Sender<String>,
+    /// Descriptions of failures.
+    failures: mpsc::Receiver<String>,
+}
+
+impl WatchHealth {
+    /// Start monitoring the health of the watch.
+    pub fn start() -> Self {
+        let (sender, failures) = mpsc::channel();
+
+        if let Ok(mut panic_sender) = PANIC_SENDER.lock() {
+            *panic_sender = Some(sender.clone());
+        }
+        PANIC_HOOK.call_once(|| {
+            let default_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                default_hook(info);
+                if std::thread::current().name() == Some("main") {
+                    return;
+                }
+                if let Ok(panic_sender) = PANIC_SENDER.lock() {
+                    if let Some(sender) = panic_sender.as_ref() {
+                        drop(sender.send(format!("background thread panicked: {info}")));
+                    }
+                }
+            }));
+        });
+
+        Self { sender, failures }
+    }
+
+    /// Where the watcher reports its failures.
+    pub fn reporter(&self) -> mpsc::Sender<String> {
+        self.sender.clone()
+    }
+
+    /// Block until the watch fails, returning a description of the failure.
+    pub fn wait_for_failure(&self) -> String {
+        self.failures
+            .recv()
+            .unwrap_or_else(|_| "watch health monitor stopped".to_owned())
+    }
+}
@@ -0,0 +1,67 @@
+//! Log the raw filesystem events in the shader crate, for debugging why saves do or don't
+//! trigger compiles. This is a second watcher, on every file in the crate, so that the events
+//! that the daemon's watcher ignores, as they aren't sources, are logged too.
+
+use std::io::Write as _;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use notify::Watcher as _;
+use serde_json::json;
+
+/// Append a line of JSON to the log for every event in the crate, except for those only in its
+/// `target/` directory, which change with every compile. The returned watcher must be kept alive
+/// for as long as events should be logged.
+pub fn start(crate_path: &Path, log_path: &Path) -> anyhow::Result<notify::RecommendedWatcher> {
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("Couldn't open watch events log '{}'", log_path.display()))?;
+    let log = Mutex::new(log);
+    let target_dir = crate_path
+        .canonicalize()
+        .unwrap_or_else(|_| crate_path.to_path_buf())
+        .join("target");
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let entry = match event {
+            Ok(event) => {
+                if !event.paths.is_empty()
+                    && event.paths.iter().all(|path| path.starts_with(&target_dir))
+                {
+                    return;
+                }
+                let paths: Vec<String> = event
+                    .paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                json!({
+                    "timestamp_ms": timestamp,
+                    "kind": format!("{:?}", event.kind),
+                    "paths": paths,
+                })
+            }
+            Err(error) => json!({
+                "timestamp_ms": timestamp,
+                "error": error.to_string(),
+            }),
+        };
+
+        if let Ok(mut file) = log.lock() {
+            if let Err(error) = writeln!(file, "{entry}") {
+                tracing::error!("Couldn't write to watch events log: {error:?}");
+            }
+        }
+    })?;
+    watcher.watch(crate_path, notify::RecursiveMode::Recursive)?;
+    tracing::info!("Logging watch events to {log_path:?}");
+
+    Ok(watcher)
+}
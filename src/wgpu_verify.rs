@@ -0,0 +1,36 @@
+//! Check that `wgpu` itself accepts a compiled module, by creating a shader module on a real
+//! device. This exercises the runtime path, which can catch issues that `naga` validation alone
+//! doesn't.
+
+/// Create a headless `wgpu` device and load the SPIR-V module on it, failing with any error
+/// `wgpu` raises. Skipped, with a warning, if there's no adapter, as on most CI machines.
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn verify(path: &std::path::Path) -> anyhow::Result<()> {
+    let bytes = std::fs::read(path)?;
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let Some(adapter) =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+    else {
+        tracing::warn!("No wgpu adapter available, skipping `--verify-wgpu`");
+        return Ok(());
+    };
+    let (device, _queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))?;
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let _module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: path.to_str(),
+        source: wgpu::util::make_spirv(&bytes),
+    });
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        anyhow::bail!("wgpu rejected the shader module: {error}");
+    }
+
+    tracing::info!(
+        "  wgpu accepted the shader module on '{}'",
+        adapter.get_info().name
+    );
+
+    Ok(())
+}